@@ -0,0 +1,301 @@
+use crate::cube::{Cube, SOLVED};
+use crate::mv::{Metric, Move, ParseAlgorithmError};
+
+/// a sequence of moves
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Algorithm(pub Vec<Move>);
+
+/// the plane an algorithm is reflected through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mirror {
+    /// swaps the L and R faces
+    LeftRight,
+    /// swaps the F and B faces
+    FrontBack,
+}
+
+impl Algorithm {
+    pub fn moves(&self) -> &[Move] {
+        &self.0
+    }
+
+    /// length of the algorithm counted in the given metric
+    pub fn length(&self, metric: Metric) -> usize {
+        self.0.iter().map(|m| m.cost(metric) as usize).sum()
+    }
+
+    /// the algorithm that undoes this one
+    pub fn inverse(&self) -> Self {
+        Self(self.0.iter().rev().map(|m| m.inverse()).collect())
+    }
+
+    /// reflects the algorithm, swapping the two faces on either side of the plane and reversing
+    /// the direction of every turn
+    pub fn mirror(&self, plane: Mirror) -> Self {
+        use Move::*;
+
+        // the slice and rotation lying on the axis through the swapped faces keep their direction
+        let (a, b, kept) = match plane {
+            Mirror::LeftRight => (L.layer(), R.layer(), [M.layer(), X.layer()]),
+            Mirror::FrontBack => (F.layer(), B.layer(), [S.layer(), Z.layer()]),
+        };
+
+        Self(
+            self.0
+                .iter()
+                .map(|&m| {
+                    // wide turns are laid out the same way as the faces, 6 layers later
+                    let layer = match m.layer() {
+                        l if l < 12 && l % 6 == a => l - a + b,
+                        l if l < 12 && l % 6 == b => l - b + a,
+                        l => l,
+                    };
+                    let turns = match kept.contains(&layer) {
+                        true => m.quarter_turns(),
+                        false => 4 - m.quarter_turns(),
+                    };
+                    Move::from_layer(layer, turns).unwrap()
+                })
+                .collect(),
+        )
+    }
+
+    /// merges consecutive turns of the same layer, including across a turn on the same axis since
+    /// those commute (R R2 -> R', U D U -> U2 D), and removes turns that cancel out
+    pub fn simplify(&self) -> Self {
+        let mut moves: Vec<Move> = Vec::with_capacity(self.0.len());
+
+        for &mv in &self.0 {
+            // the move to merge with is either the last one, or the one before it when the last
+            // move is on the same axis
+            let target = match moves[..] {
+                [.., x] if x.layer() == mv.layer() => Some(moves.len() - 1),
+                [.., x, y] if x.layer() == mv.layer() && y.axis() == mv.axis() => {
+                    Some(moves.len() - 2)
+                }
+                _ => None,
+            };
+
+            match target {
+                Some(i) => {
+                    let turns = moves[i].quarter_turns() + mv.quarter_turns();
+                    match Move::from_layer(mv.layer(), turns) {
+                        Some(merged) => moves[i] = merged,
+                        None => {
+                            moves.remove(i);
+                        }
+                    }
+                }
+                None => moves.push(mv),
+            }
+        }
+
+        Self(moves)
+    }
+
+    /// rewrites pairs of opposite face turns that leave the middle layer behind (R L', U2 D2) as a
+    /// single slice turn, the result is the same as this algorithm up to a whole cube rotation
+    pub fn with_slices(&self) -> Self {
+        use Move::*;
+
+        // the moves after a slice are turned into the ones that have the same effect on the cube
+        // as it's now held, `frame` is the rotation done by all the slices so far
+        let relabel = |frame: &Cube, mv: Move| {
+            let conjugated = frame.apply(&mv.to_cube()).apply(&frame.inverse());
+            *Move::EVERY
+                .iter()
+                .find(|m| m.to_cube() == conjugated)
+                .unwrap()
+        };
+
+        let mut frame = SOLVED;
+        let mut moves = Vec::with_capacity(self.0.len());
+        let mut iter = self.0.iter().peekable();
+
+        while let Some(&mv) = iter.next() {
+            let a = relabel(&frame, mv);
+            let pair = iter.peek().map(|&&next| relabel(&frame, next)).filter(|b| {
+                a.is_face_turn()
+                    && b.is_face_turn()
+                    && a.axis() == b.axis()
+                    && a.layer() != b.layer()
+                    && (a.quarter_turns() + b.quarter_turns()).is_multiple_of(4)
+            });
+
+            let Some(b) = pair else {
+                moves.push(a);
+                continue;
+            };
+            iter.next();
+
+            // R L' is M x, U D' is E y and F B' is S' z
+            let (positive, (slice, rotation, same)) = match (a.layer(), b.layer()) {
+                (3, _) | (_, 3) => (a.max(b), (M, X, true)),
+                (0, _) | (_, 0) => (a.min(b), (E, Y, true)),
+                _ => (a.min(b), (S, Z, false)),
+            };
+            let turns = positive.quarter_turns();
+            let slice_turns = if same { turns } else { 4 - turns };
+
+            moves.push(Move::from_layer(slice.layer(), slice_turns).unwrap());
+            frame = Move::from_layer(rotation.layer(), turns)
+                .unwrap()
+                .to_cube()
+                .apply(&frame);
+        }
+
+        Self(moves)
+    }
+
+    /// the state reached by applying the algorithm to a solved cube
+    pub fn to_cube(&self) -> Cube {
+        self.0.iter().map(|m| m.to_cube()).product()
+    }
+}
+
+impl From<Vec<Move>> for Algorithm {
+    fn from(moves: Vec<Move>) -> Self {
+        Self(moves)
+    }
+}
+
+impl FromIterator<Move> for Algorithm {
+    fn from_iter<I: IntoIterator<Item = Move>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for Algorithm {
+    type Item = Move;
+    type IntoIter = std::vec::IntoIter<Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl std::fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, mv) in self.0.iter().enumerate() {
+            if i != 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{mv}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Algorithm {
+    type Err = ParseAlgorithmError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        crate::mv::parse_algorithm(s).map(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alg(s: &str) -> Algorithm {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn inverse() {
+        let scramble = alg("R U2 F' L B D R");
+
+        assert_eq!(scramble.inverse(), alg("R' D' B' L' F U2 R'"));
+        assert_eq!(scramble.to_cube() * scramble.inverse().to_cube(), SOLVED);
+    }
+
+    #[test]
+    fn mirror() {
+        let sexy = alg("R U R' U'");
+
+        assert_eq!(sexy.mirror(Mirror::LeftRight), alg("L' U' L U"));
+        assert_eq!(sexy.mirror(Mirror::FrontBack), alg("R' U' R U"));
+        assert_eq!(
+            alg("F R2 B' D").mirror(Mirror::FrontBack),
+            alg("B' R2 F D'")
+        );
+
+        let scramble = alg("R U2 F' L B D R");
+        for plane in [Mirror::LeftRight, Mirror::FrontBack] {
+            assert_eq!(scramble.mirror(plane).mirror(plane), scramble);
+        }
+    }
+
+    #[test]
+    fn mirror_slices() {
+        let slices = alg("M' U M U2 Rw x y' S E2 Lw'");
+
+        assert_eq!(
+            slices.mirror(Mirror::LeftRight),
+            alg("M' U' M U2 Lw' x y S' E2 Rw")
+        );
+        assert_eq!(
+            slices.mirror(Mirror::FrontBack),
+            alg("M U' M' U2 Rw' x' y S E2 Lw")
+        );
+    }
+
+    #[test]
+    fn with_slices() {
+        let pairs = [
+            ("R L' U2", "M F2"),
+            ("R2 L2 U R2 L2", "M2 D M2"),
+            ("L' R F U D' F", "M D S' R"),
+            ("F B' R", "S' U"),
+            ("R U F", "R U F"),
+        ];
+
+        for (input, expected) in pairs {
+            let sliced = alg(input).with_slices();
+            assert_eq!(sliced, alg(expected), "{input}");
+
+            // undoing the rotation left by the slices gives back the original state
+            let state = sliced.to_cube();
+            let state = state.apply(&state.rotation().inverse());
+            assert_eq!(state, alg(input).to_cube(), "{input}");
+        }
+    }
+
+    #[test]
+    fn simplify() {
+        let pairs = [
+            ("R R2", "R'"),
+            ("R R'", ""),
+            ("U D U", "U2 D"),
+            ("U D U'", "D"),
+            ("F R U U' R' F'", ""),
+            ("R L R2 L R", "L2"),
+            ("U D F B F'", "U D B"),
+            ("R U F", "R U F"),
+        ];
+
+        for (input, expected) in pairs {
+            let simplified = alg(input).simplify();
+            assert_eq!(simplified, alg(expected), "{input}");
+            assert_eq!(simplified.to_cube(), alg(input).to_cube(), "{input}");
+        }
+    }
+
+    #[test]
+    fn length() {
+        let scramble = alg("R U2 F' L2 B");
+
+        assert_eq!(scramble.length(Metric::Htm), 5);
+        assert_eq!(scramble.length(Metric::Qtm), 7);
+        assert_eq!(scramble.length(Metric::Stm), 5);
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let scramble = alg("R U2 F' L B3 D R");
+
+        assert_eq!(scramble.to_string(), "R U2 F' L B' D R");
+        assert_eq!(alg(&scramble.to_string()), scramble);
+    }
+}
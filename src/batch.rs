@@ -0,0 +1,234 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crate::algorithm::Algorithm;
+use crate::cube::Cube;
+use crate::heuristics::Heuristic;
+use crate::mv::Metric;
+use crate::parallel::par_ida;
+use crate::progress::with_progress;
+use crate::search::{CancelToken, SearchLimits};
+
+/// how each cube of a batch is searched
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    pub metric: Metric,
+    pub max_depth: u8,
+    /// how many cubes are solved at once
+    pub jobs: usize,
+    /// the threads each of those searches uses
+    pub threads: usize,
+    /// how long each cube can take
+    pub time_limit: Option<Duration>,
+    /// how many nodes each cube can take
+    pub max_nodes: Option<u64>,
+    /// stops the whole batch, the cubes not yet solved are left without a solution
+    pub cancel: Option<CancelToken>,
+}
+
+/// how solving one cube of a batch went
+#[derive(Debug, Clone)]
+pub struct BatchResult {
+    /// `None` when there's no solution within the depth or time limit
+    pub solution: Option<Algorithm>,
+    pub nodes: u64,
+    /// every solution costs at least this much, see `SearchResult::lower_bound`
+    pub lower_bound: u8,
+    pub elapsed: Duration,
+}
+
+/// solves every cube optimally, `jobs` at a time sharing the one heuristic. `on_result` is called
+/// on this thread with the index of each cube as soon as it's solved, and the results are returned
+/// in the order of `cubes`. The searches don't report their own progress
+pub fn solve_batch<H>(
+    cubes: &[Cube],
+    options: &BatchOptions,
+    h: H,
+    mut on_result: impl FnMut(usize, &BatchResult),
+) -> Vec<BatchResult>
+where
+    H: Heuristic<Cube> + Send + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let mut results = vec![None; cubes.len()];
+
+    std::thread::scope(|s| {
+        let (sender, receiver) = mpsc::channel();
+        for _ in 0..options.jobs.clamp(1, cubes.len().max(1)) {
+            let sender = sender.clone();
+            let next = &next;
+            s.spawn(move || {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(cube) = cubes.get(index) else {
+                        break;
+                    };
+
+                    let start = Instant::now();
+                    let limits = SearchLimits {
+                        deadline: options.time_limit.map(|limit| start + limit),
+                        max_nodes: options.max_nodes,
+                        cancel: options.cancel.clone(),
+                        observer: None,
+                    };
+                    let result = with_progress(Arc::new(|_| {}), || {
+                        par_ida(
+                            cube.normalised(),
+                            options.max_depth,
+                            options.metric,
+                            options.threads,
+                            &limits,
+                            h,
+                        )
+                    });
+                    let result = BatchResult {
+                        nodes: result.nodes,
+                        lower_bound: result.lower_bound,
+                        solution: result.into_solution().map(Algorithm::from),
+                        elapsed: start.elapsed(),
+                    };
+                    if sender.send((index, result)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        for (index, result) in receiver {
+            on_result(index, &result);
+            results[index] = Some(result);
+        }
+    });
+
+    results
+        .into_iter()
+        .map(|result| result.expect("every cube is searched"))
+        .collect()
+}
+
+/// statistics over a batch of results
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchSummary {
+    pub solved: usize,
+    pub unsolved: usize,
+    /// how many solutions have each length in the metric
+    pub lengths: BTreeMap<usize, usize>,
+    /// of the time each cube took, solved or not
+    pub mean_time: Duration,
+    pub median_time: Duration,
+    pub total_nodes: u64,
+    /// the time the whole batch took
+    pub elapsed: Duration,
+    /// all the nodes searched over the time the whole batch took
+    pub nodes_per_second: f64,
+}
+
+impl BatchSummary {
+    pub fn new(results: &[BatchResult], metric: Metric, elapsed: Duration) -> Self {
+        let mut lengths = BTreeMap::new();
+        for solution in results.iter().filter_map(|r| r.solution.as_ref()) {
+            *lengths.entry(solution.length(metric)).or_insert(0) += 1;
+        }
+        let solved = lengths.values().sum();
+
+        let mut times: Vec<Duration> = results.iter().map(|r| r.elapsed).collect();
+        times.sort();
+        let median_time = match times.len() {
+            0 => Duration::ZERO,
+            n if n % 2 == 1 => times[n / 2],
+            n => (times[n / 2 - 1] + times[n / 2]) / 2,
+        };
+        let mean_time = match times.len() {
+            0 => Duration::ZERO,
+            n => times.iter().sum::<Duration>() / n as u32,
+        };
+        let total_nodes = results.iter().map(|r| r.nodes).sum();
+
+        BatchSummary {
+            solved,
+            unsolved: results.len() - solved,
+            lengths,
+            mean_time,
+            median_time,
+            total_nodes,
+            elapsed,
+            nodes_per_second: total_nodes as f64 / elapsed.as_secs_f64(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heuristics::ZeroBound;
+    use crate::puzzle::Puzzle;
+
+    #[test]
+    fn solves_in_order() {
+        let scrambles = ["R U", "F", "U R2 F", "", "L D'"];
+        let cubes: Vec<Cube> = scrambles
+            .iter()
+            .map(|s| s.parse::<Algorithm>().unwrap().to_cube())
+            .collect();
+        let options = BatchOptions {
+            metric: Metric::Htm,
+            max_depth: 4,
+            jobs: 3,
+            threads: 1,
+            time_limit: None,
+            max_nodes: None,
+            cancel: None,
+        };
+
+        let mut seen = Vec::new();
+        let results = solve_batch(&cubes, &options, ZeroBound, |i, _| seen.push(i));
+        seen.sort();
+        assert_eq!(seen, [0, 1, 2, 3, 4]);
+        for (cube, result) in cubes.iter().zip(&results) {
+            let solution = result.solution.as_ref().unwrap();
+            assert!((cube.clone() * solution.to_cube()).is_solved());
+        }
+
+        let summary = BatchSummary::new(&results, Metric::Htm, Duration::from_secs(1));
+        assert_eq!((summary.solved, summary.unsolved), (5, 0));
+        assert_eq!(
+            summary.lengths,
+            BTreeMap::from([(0, 1), (1, 1), (2, 2), (3, 1)])
+        );
+        assert_eq!(
+            summary.total_nodes,
+            results.iter().map(|r| r.nodes).sum::<u64>()
+        );
+    }
+
+    #[test]
+    fn summary() {
+        let result = |length: Option<&str>, ms| BatchResult {
+            solution: length.map(|s| s.parse().unwrap()),
+            nodes: 100,
+            lower_bound: 0,
+            elapsed: Duration::from_millis(ms),
+        };
+        let results = [
+            result(Some("R U"), 40),
+            result(None, 10),
+            result(Some("R2 U"), 30),
+            result(Some("F"), 20),
+        ];
+
+        let summary = BatchSummary::new(&results, Metric::Qtm, Duration::from_millis(200));
+        assert_eq!((summary.solved, summary.unsolved), (3, 1));
+        assert_eq!(summary.lengths, BTreeMap::from([(1, 1), (2, 1), (3, 1)]));
+        assert_eq!(summary.mean_time, Duration::from_millis(25));
+        assert_eq!(summary.median_time, Duration::from_millis(25));
+        assert_eq!(summary.nodes_per_second, 2000.0);
+        assert_eq!(
+            BatchSummary::new(&results[..3], Metric::Htm, Duration::from_secs(1)).median_time,
+            Duration::from_millis(30)
+        );
+    }
+}
@@ -1,23 +1,29 @@
 use colored::Colorize;
 
+use super::facelets::FaceletError;
 use super::heuristics::Heuristic;
-use super::mv::Move;
+use super::mv::{Metric, Move};
+use super::progress::progress;
+use super::search::{
+    AbortReason, CHECK_INTERVAL, Heartbeats, SearchEvent, SearchLimits, SearchOutcome, SearchResult,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Cube {
-    pub(crate) eo: [u8; 12], // all <2
-    pub(crate) ep: [u8; 12], // all unique, all <2
-    pub(crate) co: [u8; 8],  // all <3
-    pub(crate) cp: [u8; 8],  // all unique, all <8
+    pub(crate) eo: [u8; 12],     // all <2
+    pub(crate) ep: [u8; 12],     // all unique, all <2
+    pub(crate) co: [u8; 8],      // all <3
+    pub(crate) cp: [u8; 8],      // all unique, all <8
+    pub(crate) centers: [u8; 6], // all unique, all <6, in the order U D L R F B
 }
 
 pub enum FaceletAssociation {
-    Center(Color),
+    Center(u8),
     Edge(u8, u8),   // EP, EO
     Corner(u8, u8), // CP, CO
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Color {
     White,
     Yellow,
@@ -31,31 +37,198 @@ pub fn ida<T: Puzzle + Clone>(puzzle: T, max_depth: u8, h: impl Heuristic<T>) ->
 where
     for<'a> &'a T: std::ops::Mul<Move, Output = T>,
 {
-    for depth in h.lower_bound(&puzzle)..=max_depth {
-        eprintln!("starting depth {depth}...");
+    ida_with_metric(puzzle, max_depth, Metric::Htm, h)
+}
+
+/// `ida` minimising the cost of the solution in `metric` instead of its length, `h` has to bound
+/// the cost in that metric
+pub fn ida_with_metric<T: Puzzle + Clone, H: Heuristic<T>>(
+    puzzle: T,
+    max_cost: u8,
+    metric: Metric,
+    h: H,
+) -> Option<Vec<Move>>
+where
+    for<'a> &'a T: std::ops::Mul<Move, Output = T>,
+{
+    ida_with_limits(puzzle, max_cost, metric, &SearchLimits::default(), h).into_solution()
+}
+
+/// `ida_with_metric` that gives up once it reaches one of the `limits`, and sends their observer
+/// the search's events
+pub fn ida_with_limits<T: Puzzle + Clone, H: Heuristic<T>>(
+    puzzle: T,
+    max_cost: u8,
+    metric: Metric,
+    limits: &SearchLimits,
+    h: H,
+) -> SearchResult
+where
+    for<'a> &'a T: std::ops::Mul<Move, Output = T>,
+{
+    // a rotated puzzle is solved once it's solved relative to its centers
+    let puzzle = puzzle.reoriented();
+    let memo = h.memo(&puzzle);
+    let first = h.memo_bound(memo);
+    let mut lower_bound = first;
+    let mut nodes = Nodes {
+        branches: 0,
+        leaves: 0,
+        before: 0,
+        limits,
+        aborted: None,
+        heartbeats: Heartbeats::new(limits),
+    };
+
+    for depth in first..=max_cost {
+        progress!("starting depth {depth}...");
+        limits.send(|| SearchEvent::DepthStarted { depth });
         let start = std::time::Instant::now();
-        let mut nodes = (0, 0);
+        nodes.before += nodes.branches + nodes.leaves;
+        (nodes.branches, nodes.leaves) = (0, 0);
 
-        let puzzle = puzzle.clone();
-        let path = dfs(0, Vec::new(), depth, puzzle, &mut nodes, h);
+        let mut path = Vec::new();
+        let found = dfs(0, &mut path, depth, metric, &puzzle, memo, &mut nodes, h);
         let elapsed = start.elapsed();
-        let (branches, leaves) = nodes;
-        eprintln!(
+        let Nodes {
+            branches, leaves, ..
+        } = nodes;
+        let branching_factor = if branches != 0 {
+            (branches + leaves - 1) as f64 / branches as f64
+        } else {
+            0.0
+        };
+        progress!(
             "searched {} nodes in {:.2?} at {:.2}M nodes/s, branching factor: {:.2}",
             branches + leaves,
             elapsed,
             (branches + leaves) as f64 / elapsed.as_secs_f64() / 1_000_000.0,
-            if branches != 0 {
-                (branches + leaves - 1) as f64 / branches as f64
-            } else {
-                0.0
-            }
+            branching_factor
+        );
+
+        let searched = nodes.before + branches + leaves;
+        if found {
+            limits.send(|| SearchEvent::SolutionFound {
+                solution: path.clone(),
+                nodes: searched,
+                elapsed: nodes.heartbeats.elapsed(),
+            });
+            return SearchResult {
+                outcome: SearchOutcome::Solved(path),
+                nodes: searched,
+                lower_bound: depth,
+            };
+        }
+        if let Some(reason) = nodes.aborted {
+            return SearchResult {
+                outcome: SearchOutcome::Aborted(reason),
+                nodes: searched,
+                lower_bound,
+            };
+        }
+        limits.send(|| SearchEvent::DepthFinished {
+            depth,
+            nodes: branches + leaves,
+            elapsed,
+            branching_factor,
+        });
+        lower_bound = depth + 1;
+    }
+
+    SearchResult {
+        outcome: SearchOutcome::NoSolution,
+        nodes: nodes.before + nodes.branches + nodes.leaves,
+        lower_bound,
+    }
+}
+
+/// the nodes `dfs` has searched at the current depth, split into those it branched from and the
+/// leaves, and the limits it stops at
+pub struct Nodes<'a> {
+    pub branches: u64,
+    pub leaves: u64,
+    /// the nodes searched at earlier depths, which count towards `SearchLimits::max_nodes`
+    pub before: u64,
+    pub limits: &'a SearchLimits,
+    /// set when a limit stops the search
+    pub aborted: Option<AbortReason>,
+    /// when the search started and when its next heartbeat is due
+    pub heartbeats: Heartbeats,
+}
+
+/// every solution of the shortest length, and of up to `extra` moves more, stopping once `cap`
+/// solutions are found. Moves on opposite faces commute, so they're only tried in one order and
+/// solutions that only differ in the order of such moves are returned once.
+pub fn ida_all<T: Puzzle + Clone>(
+    puzzle: T,
+    max_depth: u8,
+    extra: u8,
+    cap: usize,
+    h: impl Heuristic<T>,
+) -> Vec<Vec<Move>>
+where
+    for<'a> &'a T: std::ops::Mul<Move, Output = T>,
+{
+    let puzzle = puzzle.reoriented();
+    let mut solutions = Vec::new();
+    let mut optimal = None;
+    for depth in h.lower_bound(&puzzle)..=max_depth {
+        if optimal.is_some_and(|optimal| depth > optimal + extra) || solutions.len() >= cap {
+            break;
+        }
+
+        progress!("starting depth {depth}...");
+        let start = std::time::Instant::now();
+        let found = solutions.len();
+        dfs_all(&puzzle, &mut Vec::new(), depth, cap, &mut solutions, h);
+        progress!(
+            "found {} solutions of length {depth} in {:.2?}",
+            solutions.len() - found,
+            start.elapsed()
         );
-        if let Some(path) = path {
-            return Some(path);
+
+        if optimal.is_none() && solutions.len() > found {
+            optimal = Some(depth);
+        }
+    }
+    solutions
+}
+
+fn dfs_all<T: Puzzle>(
+    puzzle: &T,
+    path: &mut Vec<Move>,
+    remaining: u8,
+    cap: usize,
+    solutions: &mut Vec<Vec<Move>>,
+    h: impl Heuristic<T>,
+) where
+    for<'a> &'a T: std::ops::Mul<Move, Output = T>,
+{
+    if remaining == 0 {
+        if puzzle.is_solved() {
+            solutions.push(path.clone());
+        }
+        return;
+    }
+
+    // a path through the solved state contains a shorter solution
+    if h.lower_bound(puzzle) > remaining || (!path.is_empty() && puzzle.is_solved()) {
+        return;
+    }
+
+    for &mv in Move::ALL {
+        if path.last().is_some_and(|&last| mv.skip_after(last)) {
+            continue;
+        }
+
+        path.push(mv);
+        dfs_all(&(puzzle * mv), path, remaining - 1, cap, solutions, h);
+        path.pop();
+
+        if solutions.len() >= cap {
+            return;
         }
     }
-    None
 }
 
 use crate::puzzle::Puzzle;
@@ -68,42 +241,87 @@ impl Puzzle for Cube {
     fn is_solved(&self) -> bool {
         self == &Self::solved()
     }
+
+    fn reoriented(self) -> Self {
+        if self.centers == SOLVED.centers {
+            self
+        } else {
+            self.normalised()
+        }
+    }
 }
 
-pub fn dfs<T: Puzzle>(
-    depth: u8,
-    path: Vec<Move>,
-    max_depth: u8,
-    puzzle: T,
-    nodes: &mut (u64, u64),
-    h: impl Heuristic<T>,
-) -> Option<Vec<Move>>
+// `cost` is what `path` costs in `metric`, which is the depth in the half turn metric. The
+// solution is left in `path` when one is found
+#[allow(clippy::too_many_arguments)]
+pub fn dfs<T: Puzzle, H: Heuristic<T>>(
+    cost: u8,
+    path: &mut Vec<Move>,
+    max_cost: u8,
+    metric: Metric,
+    puzzle: &T,
+    memo: H::Memo,
+    nodes: &mut Nodes,
+    h: H,
+) -> bool
 where
     for<'a> &'a T: std::ops::Mul<Move, Output = T>,
 {
-    if depth >= max_depth {
-        nodes.1 += 1;
-        if puzzle.is_solved() { Some(path) } else { None }
-    } else if depth + h.lower_bound(&puzzle) > max_depth {
-        None
+    let searched = nodes.before + nodes.branches + nodes.leaves;
+    if nodes.aborted.is_some() {
+        return false;
+    }
+    // the node limit is cheap to check every time, the clock isn't
+    let interval = searched.is_multiple_of(CHECK_INTERVAL);
+    if interval
+        || nodes
+            .limits
+            .max_nodes
+            .is_some_and(|max_nodes| searched >= max_nodes)
+    {
+        nodes.aborted = nodes.limits.check(searched);
+        if nodes.aborted.is_some() {
+            return false;
+        }
+    }
+    if interval {
+        nodes.heartbeats.tick(nodes.limits, searched, path);
+    }
+
+    if cost >= max_cost {
+        nodes.leaves += 1;
+        puzzle.is_solved()
+    } else if cost + h.memo_bound(memo) > max_cost {
+        false
     } else {
         if let [.., x, y] = &path[..] {
             if x.redundant(*y) {
-                return None;
+                return false;
             }
         }
 
-        nodes.0 += 1;
+        nodes.branches += 1;
+
+        for &m in metric.moves() {
+            let cost = cost + m.cost(metric);
+            if cost > max_cost {
+                continue;
+            }
 
-        Move::ALL.into_iter().find_map(|&m| {
-            let mut path = path.clone();
             path.push(m);
-            dfs(depth + 1, path, max_depth, &puzzle * m, nodes, h)
-        })
+            let puzzle = (puzzle * m).reoriented();
+            let memo = h.next_memo(&puzzle, memo);
+            if dfs(cost, path, max_cost, metric, &puzzle, memo, nodes, h) {
+                return true;
+            }
+            path.pop();
+        }
+
+        false
     }
 }
 
-fn edge_colors(edge: u8) -> &'static [Color; 2] {
+pub(crate) fn edge_colors(edge: u8) -> &'static [Color; 2] {
     use Color::*;
 
     match edge {
@@ -123,7 +341,21 @@ fn edge_colors(edge: u8) -> &'static [Color; 2] {
     }
 }
 
-fn corner_colors(corner: u8) -> &'static [Color; 3] {
+pub(crate) fn center_color(center: u8) -> Color {
+    use Color::*;
+
+    match center {
+        0 => White,
+        1 => Yellow,
+        2 => Orange,
+        3 => Red,
+        4 => Green,
+        5 => Blue,
+        _ => panic!("Invalid center number: {}", center),
+    }
+}
+
+pub(crate) fn corner_colors(corner: u8) -> &'static [Color; 3] {
     use Color::*;
 
     match corner {
@@ -140,7 +372,7 @@ fn corner_colors(corner: u8) -> &'static [Color; 3] {
 }
 
 // converts a facelet id into a cubie + orientation
-fn associate_facelet(facelet: u8) -> FaceletAssociation {
+pub(crate) fn associate_facelet(facelet: u8) -> FaceletAssociation {
     use FaceletAssociation::*;
 
     match facelet {
@@ -149,7 +381,7 @@ fn associate_facelet(facelet: u8) -> FaceletAssociation {
         1 => Edge(0, 0),
         2 => Corner(1, 0),
         3 => Edge(3, 0),
-        4 => Center(Color::White),
+        4 => Center(0),
         5 => Edge(1, 0),
         6 => Corner(3, 0),
         7 => Edge(2, 0),
@@ -174,19 +406,19 @@ fn associate_facelet(facelet: u8) -> FaceletAssociation {
         // middle row
         // orange
         21 => Edge(4, 1),
-        22 => Center(Color::Orange),
+        22 => Center(2),
         23 => Edge(7, 1),
         // green
         24 => Edge(7, 0),
-        25 => Center(Color::Green),
+        25 => Center(4),
         26 => Edge(6, 0),
         // red
         27 => Edge(6, 1),
-        28 => Center(Color::Red),
+        28 => Center(3),
         29 => Edge(5, 1),
         // blue
         30 => Edge(5, 0),
-        31 => Center(Color::Blue),
+        31 => Center(5),
         32 => Edge(4, 0),
         // bottom row
         // orange
@@ -210,7 +442,7 @@ fn associate_facelet(facelet: u8) -> FaceletAssociation {
         46 => Edge(10, 0),
         47 => Corner(6, 0),
         48 => Edge(11, 0),
-        49 => Center(Color::Yellow),
+        49 => Center(1),
         50 => Edge(9, 0),
         51 => Corner(4, 0),
         52 => Edge(8, 0),
@@ -225,6 +457,7 @@ pub const SOLVED: Cube = Cube {
     ep: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
     co: [0, 0, 0, 0, 0, 0, 0, 0],
     cp: [0, 1, 2, 3, 4, 5, 6, 7],
+    centers: [0, 1, 2, 3, 4, 5],
 };
 
 pub const SUPERFLIP: Cube = Cube {
@@ -232,6 +465,7 @@ pub const SUPERFLIP: Cube = Cube {
     ep: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
     co: [0, 0, 0, 0, 0, 0, 0, 0],
     cp: [0, 1, 2, 3, 4, 5, 6, 7],
+    centers: [0, 1, 2, 3, 4, 5],
 };
 
 impl Cube {
@@ -241,7 +475,79 @@ impl Cube {
             eo: std::array::from_fn(|i| (self.eo[mv.ep[i] as usize] + mv.eo[i]) % 2),
             cp: std::array::from_fn(|i| self.cp[mv.cp[i] as usize]),
             co: std::array::from_fn(|i| (self.co[mv.cp[i] as usize] + mv.co[i]) % 3),
+            centers: std::array::from_fn(|i| self.centers[mv.centers[i] as usize]),
+        }
+    }
+
+    /// the state that undoes this one, `cube * cube.inverse()` is solved
+    pub fn inverse(&self) -> Self {
+        let mut inverse = SOLVED;
+        for i in 0..12 {
+            let ep = self.ep[i] as usize;
+            inverse.ep[ep] = i as u8;
+            inverse.eo[ep] = self.eo[i];
+        }
+        for i in 0..8 {
+            let cp = self.cp[i] as usize;
+            inverse.cp[cp] = i as u8;
+            inverse.co[cp] = (3 - self.co[i]) % 3;
+        }
+        for i in 0..6 {
+            inverse.centers[self.centers[i] as usize] = i as u8;
+        }
+        inverse
+    }
+
+    /// checks that the state can be reached by turning a solved cube
+    pub fn validate(&self) -> Result<(), CubeError> {
+        if let Some(position) = (0..12).find(|&i| self.ep[i] >= 12 || self.eo[i] >= 2) {
+            return Err(CubeError::EdgeOutOfRange(position as u8));
+        }
+        if let Some(position) = (0..8).find(|&i| self.cp[i] >= 8 || self.co[i] >= 3) {
+            return Err(CubeError::CornerOutOfRange(position as u8));
+        }
+        if let Some((piece, missing)) = duplicate(&self.ep) {
+            return Err(CubeError::DuplicateEdge { piece, missing });
+        }
+        if let Some((piece, missing)) = duplicate(&self.cp) {
+            return Err(CubeError::DuplicateCorner { piece, missing });
+        }
+        if !rotations().any(|rotation| rotation.centers == self.centers) {
+            return Err(CubeError::Centers(self.centers));
+        }
+
+        let twist = self.co.iter().sum::<u8>() % 3;
+        if twist != 0 {
+            return Err(CubeError::TwistedCorner(twist));
+        }
+        if self.eo.iter().sum::<u8>() % 2 != 0 {
+            return Err(CubeError::FlippedEdge);
+        }
+        // quarter turns of slices and whole cube rotations cycle four centers, so the center
+        // parity makes up for an odd edge cycle that has no matching corner cycle
+        if parity(&self.ep) ^ parity(&self.centers) != parity(&self.cp) {
+            return Err(CubeError::Parity);
         }
+
+        Ok(())
+    }
+
+    /// the whole cube rotation that puts the centers where they are in this state
+    pub fn rotation(&self) -> Cube {
+        self.rotation_and_inverse().0.clone()
+    }
+
+    /// the same state relative to the way the cube is currently held, so the centers are back in
+    /// their home positions and face turns have the same effect as on the rotated cube
+    pub fn normalised(&self) -> Cube {
+        self.rotation_and_inverse().1.apply(self)
+    }
+
+    fn rotation_and_inverse(&self) -> &'static (Cube, Cube) {
+        rotation_table()
+            .iter()
+            .find(|(rotation, _)| rotation.centers == self.centers)
+            .expect("centers should be a rotation of the solved centers")
     }
 
     pub fn print_net(&self) {
@@ -260,6 +566,113 @@ impl Cube {
     }
 }
 
+/// the 24 whole cube rotations
+pub(crate) fn rotations() -> impl Iterator<Item = &'static Cube> {
+    rotation_table().iter().map(|(rotation, _)| rotation)
+}
+
+/// the rotations along with their inverses, normalising happens at every node of a slice turn
+/// metric search so they're only built once
+pub(crate) fn rotation_table() -> &'static [(Cube, Cube)] {
+    use Move::*;
+
+    static ROTATIONS: std::sync::OnceLock<Vec<(Cube, Cube)>> = std::sync::OnceLock::new();
+    ROTATIONS.get_or_init(|| {
+        // any orientation is reached by bringing a face to the top then turning around it
+        [None, Some(X), Some(X2), Some(X3), Some(Z), Some(Z3)]
+            .into_iter()
+            .flat_map(|top| {
+                [None, Some(Y), Some(Y2), Some(Y3)]
+                    .into_iter()
+                    .map(move |turn| top.into_iter().chain(turn).map(Move::to_cube).product())
+            })
+            .map(|rotation: Cube| (rotation.clone(), rotation.inverse()))
+            .collect()
+    })
+}
+
+/// a piece that appears twice and the smallest piece that doesn't appear
+fn duplicate(pieces: &[u8]) -> Option<(u8, u8)> {
+    let piece = (0..pieces.len())
+        .find_map(|i| pieces[i + 1..].contains(&pieces[i]).then_some(pieces[i]))?;
+    let missing = (0..pieces.len() as u8).find(|p| !pieces.contains(p))?;
+    Some((piece, missing))
+}
+
+/// true for odd permutations
+pub(crate) fn parity(permutation: &[u8]) -> bool {
+    let mut odd = false;
+    for i in 0..permutation.len() {
+        for j in i + 1..permutation.len() {
+            odd ^= permutation[i] > permutation[j];
+        }
+    }
+    odd
+}
+
+/// why a state can't be reached from solved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CubeError {
+    /// the piece or orientation at this edge position isn't a valid value
+    EdgeOutOfRange(u8),
+    /// the piece or orientation at this corner position isn't a valid value
+    CornerOutOfRange(u8),
+    DuplicateEdge {
+        piece: u8,
+        missing: u8,
+    },
+    DuplicateCorner {
+        piece: u8,
+        missing: u8,
+    },
+    /// the centers aren't the solved centers held some way
+    Centers([u8; 6]),
+    /// the sum of the corner orientations, one corner is twisted this many times clockwise
+    TwistedCorner(u8),
+    /// one edge is flipped
+    FlippedEdge,
+    /// two pieces are swapped
+    Parity,
+    /// the stickers don't make up the pieces of a cube
+    Facelets(FaceletError),
+}
+
+impl std::fmt::Display for CubeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CubeError::EdgeOutOfRange(position) => write!(f, "invalid edge at {position}"),
+            CubeError::CornerOutOfRange(position) => write!(f, "invalid corner at {position}"),
+            CubeError::DuplicateEdge { piece, missing } => {
+                write!(
+                    f,
+                    "edge {piece} appears twice and edge {missing} is missing"
+                )
+            }
+            CubeError::DuplicateCorner { piece, missing } => {
+                write!(
+                    f,
+                    "corner {piece} appears twice and corner {missing} is missing"
+                )
+            }
+            CubeError::Centers(centers) => write!(f, "impossible centers {centers:?}"),
+            CubeError::TwistedCorner(twist) => {
+                write!(f, "a corner is twisted {twist} time(s) clockwise")
+            }
+            CubeError::FlippedEdge => write!(f, "an edge is flipped"),
+            CubeError::Parity => write!(f, "two pieces are swapped"),
+            CubeError::Facelets(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for CubeError {}
+
+impl From<FaceletError> for CubeError {
+    fn from(e: FaceletError) -> Self {
+        CubeError::Facelets(e)
+    }
+}
+
 impl std::iter::Product for Cube {
     fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
         iter.fold(SOLVED, |acc, cur| acc.apply(&cur))
@@ -311,7 +724,7 @@ impl FaceletAssociation {
                 let eoi = ((eo + cube.eo[ep as usize]) % 2) as usize;
                 edge_colors(epi)[eoi]
             }
-            FaceletAssociation::Center(color) => color,
+            FaceletAssociation::Center(center) => center_color(cube.centers[center as usize]),
         }
     }
 }
@@ -432,6 +845,202 @@ mod tests {
         assert_eq!(SOLVED * R * R2 * R, SOLVED)
     }
 
+    #[test]
+    fn inverse() {
+        let scramble = R * U2 * F3 * L * B * D * R;
+        assert_eq!(scramble.apply(&scramble.inverse()), SOLVED);
+        assert_eq!(scramble.inverse().apply(&scramble), SOLVED);
+    }
+
+    #[test]
+    fn slice_colors() {
+        let facelets = M.to_cube().to_facelets();
+
+        // the back face moves up, the U face center and middle column are blue
+        for i in [1, 4, 7] {
+            assert_eq!(facelets[i], Blue);
+        }
+        for i in [0, 2, 3, 5, 6, 8] {
+            assert_eq!(facelets[i], White);
+        }
+    }
+
+    #[test]
+    fn rotations() {
+        // turning a face after a rotation turns the face that was moved into its place
+        let pairs = [
+            (X * U * X3, F.to_cube()),
+            (Y * R * Y3, B.to_cube()),
+            (Z * U * Z3, L.to_cube()),
+            (X * Y * X3, Z.to_cube()),
+            (R * L3 * X3, M.to_cube()),
+            (Rw * X3, L.to_cube()),
+        ];
+        for (rotated, expected) in pairs {
+            assert_eq!(rotated, expected);
+        }
+
+        assert_eq!(X * X * X * X, SOLVED);
+        assert_eq!(X2.to_cube().to_facelets()[4], Yellow);
+    }
+
+    #[test]
+    fn normalised() {
+        assert_eq!((X * U).normalised(), U.to_cube());
+        assert_eq!((M * U).normalised().rotation(), SOLVED);
+        assert_eq!((Y2 * Z * X3 * R * F3).normalised(), R * F3);
+        // M2 flips the cube upside down so the following U turns are the D face
+        assert_eq!(
+            (M2 * U * M2 * U2 * M2 * U * M2).normalised(),
+            R2 * L2 * D * R2 * L2 * U2 * R2 * L2 * D * R2 * L2,
+        );
+    }
+
+    #[test]
+    fn validate() {
+        let scrambled: Cube = [R, U2, F3, M, L, B, D, X, R, U, F2, Z, E3, D3, S, L2]
+            .into_iter()
+            .map(crate::mv::Move::to_cube)
+            .product();
+        assert_eq!(SOLVED.validate(), Ok(()));
+        assert_eq!(SUPERFLIP.validate(), Ok(()));
+        assert_eq!(scrambled.validate(), Ok(()));
+
+        let mut cube = scrambled.clone();
+        cube.co[3] = (cube.co[3] + 2) % 3;
+        assert_eq!(cube.validate(), Err(CubeError::TwistedCorner(2)));
+
+        let mut cube = scrambled.clone();
+        cube.eo[5] ^= 1;
+        assert_eq!(cube.validate(), Err(CubeError::FlippedEdge));
+
+        let mut cube = scrambled.clone();
+        cube.ep.swap(0, 1);
+        assert_eq!(cube.validate(), Err(CubeError::Parity));
+        cube.cp.swap(0, 1);
+        assert_eq!(cube.validate(), Ok(()));
+
+        let mut cube = SOLVED;
+        cube.ep[4] = 2;
+        assert_eq!(
+            cube.validate(),
+            Err(CubeError::DuplicateEdge {
+                piece: 2,
+                missing: 4
+            })
+        );
+
+        let mut cube = SOLVED;
+        cube.cp[7] = 8;
+        assert_eq!(cube.validate(), Err(CubeError::CornerOutOfRange(7)));
+
+        let mut cube = SOLVED;
+        cube.centers.swap(0, 1);
+        assert_eq!(cube.validate(), Err(CubeError::Centers(cube.centers)));
+    }
+
+    #[test]
+    fn all_solutions() {
+        use crate::heuristics::ZeroBound;
+
+        // R and L commute, only one order is returned
+        assert_eq!(
+            ida_all(SOLVED * R * L, 5, 2, 100, ZeroBound),
+            [vec![L3, R3]]
+        );
+
+        let scramble = SOLVED * R * U * R3 * U3;
+        let solutions = ida_all(scramble.clone(), 5, 0, 100, ZeroBound);
+        assert!(solutions.contains(&vec![U, R, U3, R3]));
+        for (i, solution) in solutions.iter().enumerate() {
+            assert_eq!(solution.len(), 4);
+            assert!(
+                solution
+                    .iter()
+                    .fold(scramble.clone(), |c, &m| c * m)
+                    .is_solved()
+            );
+            assert!(!solutions[..i].contains(solution));
+        }
+
+        assert_eq!(ida_all(scramble, 5, 0, 1, ZeroBound).len(), 1);
+    }
+
+    #[test]
+    fn metrics() {
+        use crate::heuristics::ZeroBound;
+
+        let scramble = SOLVED * R2 * U;
+        let htm = ida_with_metric(scramble.clone(), 5, Metric::Htm, ZeroBound).unwrap();
+        let qtm = ida_with_metric(scramble.clone(), 5, Metric::Qtm, ZeroBound).unwrap();
+        assert_eq!(htm, [U3, R2]);
+        assert_eq!(qtm.iter().map(|m| m.cost(Metric::Qtm)).sum::<u8>(), 3);
+
+        // a cube held another way is already solved
+        assert_eq!(ida(SOLVED * X * Y, 5, ZeroBound), Some(vec![]));
+        assert_eq!(
+            ida_all(SOLVED * Z, 5, 0, 10, ZeroBound),
+            [Vec::<Move>::new()]
+        );
+
+        // the slice move is solved with a single slice turn, which leaves the cube rotated
+        let scramble = (SOLVED * M).normalised();
+        let stm = ida_with_metric(scramble.clone(), 5, Metric::Stm, ZeroBound).unwrap();
+        assert_eq!(stm, [M3]);
+        assert!(
+            stm.iter()
+                .fold(scramble, |c, &m| c * m)
+                .normalised()
+                .is_solved()
+        );
+    }
+
+    #[test]
+    fn limits() {
+        use crate::heuristics::ZeroBound;
+        use crate::search::{AbortReason, CancelToken};
+
+        let scramble = SOLVED * R * U * F * D2 * L * B3 * U * R2;
+        let search = |limits: &SearchLimits| {
+            ida_with_limits(scramble.clone(), 20, Metric::Htm, limits, ZeroBound)
+        };
+
+        // depths 0 to 4 take about 50,000 nodes between them
+        let result = search(&SearchLimits::default().max_nodes(100_000));
+        assert_eq!(result.outcome, SearchOutcome::Aborted(AbortReason::Nodes));
+        assert_eq!((result.nodes, result.lower_bound), (100_000, 5));
+
+        let token = CancelToken::new();
+        token.cancel();
+        let result = search(&SearchLimits::default().cancel(token));
+        assert_eq!(
+            result.outcome,
+            SearchOutcome::Aborted(AbortReason::Cancelled)
+        );
+        assert_eq!(result.lower_bound, 0);
+
+        let result = ida_with_limits(
+            SOLVED * R * U,
+            1,
+            Metric::Htm,
+            &SearchLimits::default(),
+            ZeroBound,
+        );
+        assert_eq!(
+            (result.outcome, result.lower_bound),
+            (SearchOutcome::NoSolution, 2)
+        );
+        let result = ida_with_limits(
+            SOLVED * R * U,
+            3,
+            Metric::Htm,
+            &SearchLimits::default(),
+            ZeroBound,
+        );
+        assert_eq!(result.solution(), Some(&[U3, R3][..]));
+        assert_eq!(result.lower_bound, 2);
+    }
+
     #[test]
     fn superflip() {
         #[rustfmt::skip]
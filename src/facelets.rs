@@ -0,0 +1,308 @@
+use crate::cube::{
+    Color, Cube, CubeError, FaceletAssociation, SOLVED, associate_facelet, center_color,
+    corner_colors, edge_colors, rotations,
+};
+
+/// the facelet of the project's net at each position of a URFDLB facelet string, each face is
+/// read row by row as seen from the front, or with the front face towards you for U and D
+#[rustfmt::skip]
+const URFDLB: [u8; 54] = [
+    // U
+    0, 1, 2, 3, 4, 5, 6, 7, 8,
+    // R
+    15, 16, 17, 27, 28, 29, 39, 40, 41,
+    // F
+    12, 13, 14, 24, 25, 26, 36, 37, 38,
+    // D
+    45, 46, 47, 48, 49, 50, 51, 52, 53,
+    // L
+    9, 10, 11, 21, 22, 23, 33, 34, 35,
+    // B
+    18, 19, 20, 30, 31, 32, 42, 43, 44,
+];
+
+/// which character stands for which sticker color in a facelet string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorScheme(pub [(char, Color); 6]);
+
+impl ColorScheme {
+    /// the usual facelet string letters, each sticker named after the face with that center
+    pub const FACES: ColorScheme = ColorScheme([
+        ('U', Color::White),
+        ('R', Color::Red),
+        ('F', Color::Green),
+        ('D', Color::Yellow),
+        ('L', Color::Orange),
+        ('B', Color::Blue),
+    ]);
+
+    /// stickers named after their color
+    pub const COLORS: ColorScheme = ColorScheme([
+        ('W', Color::White),
+        ('R', Color::Red),
+        ('G', Color::Green),
+        ('Y', Color::Yellow),
+        ('O', Color::Orange),
+        ('B', Color::Blue),
+    ]);
+
+    /// face letters for the way `cube` is held, so a rotated cube still has U in the U center
+    fn held(cube: &Cube) -> ColorScheme {
+        // centers in URFDLB order
+        let centers = [0, 3, 4, 1, 2, 5].map(|face| center_color(cube.centers[face]));
+        ColorScheme(std::array::from_fn(|i| (Self::FACES.0[i].0, centers[i])))
+    }
+
+    pub fn color(&self, c: char) -> Option<Color> {
+        self.0
+            .iter()
+            .find(|&&(ch, _)| ch == c)
+            .map(|&(_, color)| color)
+    }
+
+    pub fn char(&self, color: Color) -> char {
+        self.0
+            .iter()
+            .find(|&&(_, col)| col == color)
+            .map(|&(ch, _)| ch)
+            .expect("color scheme should name every color")
+    }
+}
+
+impl Cube {
+    /// the inverse of `to_facelets`, fails unless the stickers describe a solvable cube
+    pub fn from_facelets(facelets: &[Color; 54]) -> Result<Cube, CubeError> {
+        for (_, color) in ColorScheme::FACES.0 {
+            let count = facelets.iter().filter(|&&c| c == color).count();
+            if count != 9 {
+                return Err(FaceletError::StickerCount { color, count }.into());
+            }
+        }
+
+        let mut edges = [[Color::White; 2]; 12];
+        let mut corners = [[Color::White; 3]; 8];
+        let mut centers = [Color::White; 6];
+        for (i, &color) in facelets.iter().enumerate() {
+            match associate_facelet(i as u8) {
+                FaceletAssociation::Center(face) => centers[face as usize] = color,
+                FaceletAssociation::Edge(ep, eo) => edges[ep as usize][eo as usize] = color,
+                FaceletAssociation::Corner(cp, co) => corners[cp as usize][co as usize] = color,
+            }
+        }
+
+        let mut cube = SOLVED;
+        for (position, colors) in edges.into_iter().enumerate() {
+            let (piece, orientation) = (0..12)
+                .flat_map(|piece| (0..2).map(move |orientation| (piece, orientation)))
+                .find(|&(piece, orientation)| {
+                    (0..2).all(|i| edge_colors(piece)[(i + orientation) % 2] == colors[i])
+                })
+                .ok_or(FaceletError::Edge {
+                    position: position as u8,
+                    colors,
+                })?;
+            cube.ep[position] = piece;
+            cube.eo[position] = orientation as u8;
+        }
+
+        for (position, colors) in corners.into_iter().enumerate() {
+            let (piece, orientation) = (0..8)
+                .flat_map(|piece| (0..3).map(move |orientation| (piece, orientation)))
+                .find(|&(piece, orientation)| {
+                    (0..3).all(|i| corner_colors(piece)[(i + orientation) % 3] == colors[i])
+                })
+                .ok_or(FaceletError::Corner {
+                    position: position as u8,
+                    colors,
+                })?;
+            cube.cp[position] = piece;
+            cube.co[position] = orientation as u8;
+        }
+
+        for (face, color) in centers.into_iter().enumerate() {
+            cube.centers[face] = (0..6).find(|&c| center_color(c) == color).unwrap();
+        }
+        if !rotations().any(|rotation| rotation.centers == cube.centers) {
+            return Err(FaceletError::Centers(centers).into());
+        }
+
+        cube.validate()?;
+        Ok(cube)
+    }
+
+    /// reads a 54 character URFDLB facelet string using the usual face letters, whitespace is
+    /// ignored
+    pub fn from_facelet_str(s: &str) -> Result<Cube, CubeError> {
+        Self::from_facelet_str_with(s, &ColorScheme::FACES)
+    }
+
+    /// reads a URFDLB facelet string with the sticker colors given by `scheme`
+    pub fn from_facelet_str_with(s: &str, scheme: &ColorScheme) -> Result<Cube, CubeError> {
+        let chars: Vec<char> = s.chars().filter(|c| !c.is_whitespace()).collect();
+        if chars.len() != 54 {
+            return Err(FaceletError::Length(chars.len()).into());
+        }
+
+        let mut facelets = [Color::White; 54];
+        for (i, &c) in chars.iter().enumerate() {
+            facelets[URFDLB[i] as usize] = scheme.color(c).ok_or(FaceletError::UnknownFacelet {
+                index: i,
+                facelet: c,
+            })?;
+        }
+
+        Self::from_facelets(&facelets)
+    }
+
+    /// writes the URFDLB facelet string, with stickers named after the face whose center they
+    /// match in the way the cube is currently held
+    pub fn to_facelet_str(&self) -> String {
+        self.to_facelet_str_with(&ColorScheme::held(self))
+    }
+
+    pub fn to_facelet_str_with(&self, scheme: &ColorScheme) -> String {
+        let facelets = self.to_facelets();
+        URFDLB
+            .iter()
+            .map(|&i| scheme.char(facelets[i as usize]))
+            .collect()
+    }
+}
+
+/// a sticker layout that doesn't describe a cube
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FaceletError {
+    /// the string doesn't have 54 facelets
+    Length(usize),
+    /// a character the color scheme doesn't know, `index` counts facelets in the string
+    UnknownFacelet { index: usize, facelet: char },
+    /// there should be 9 stickers of each color
+    StickerCount { color: Color, count: usize },
+    /// the stickers of an edge slot don't match any edge
+    Edge { position: u8, colors: [Color; 2] },
+    /// the stickers of a corner slot don't match any corner, or are in mirrored order
+    Corner { position: u8, colors: [Color; 3] },
+    /// the centers in the order U D L R F B aren't the solved centers held some way
+    Centers([Color; 6]),
+}
+
+impl std::fmt::Display for FaceletError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FaceletError::Length(len) => write!(f, "expected 54 facelets, found {len}"),
+            FaceletError::StickerCount { color, count } => {
+                write!(f, "expected 9 {color:?} stickers, found {count}")
+            }
+            FaceletError::UnknownFacelet { index, facelet } => {
+                write!(f, "unknown facelet '{facelet}' at {index}")
+            }
+            FaceletError::Edge { position, colors } => {
+                write!(
+                    f,
+                    "no edge has the colors {colors:?} (edge slot {position})"
+                )
+            }
+            FaceletError::Corner { position, colors } => {
+                write!(
+                    f,
+                    "no corner has the colors {colors:?} (corner slot {position})"
+                )
+            }
+            FaceletError::Centers(centers) => write!(f, "impossible centers {centers:?}"),
+        }
+    }
+}
+
+impl std::error::Error for FaceletError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::Algorithm;
+    use crate::cube::{SOLVED, SUPERFLIP};
+    use crate::mv::Move;
+
+    #[test]
+    fn solved() {
+        assert_eq!(
+            SOLVED.to_facelet_str(),
+            "UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB"
+        );
+    }
+
+    #[test]
+    fn single_move() {
+        let r = "UUFUUFUUFRRRRRRRRRFFDFFDFFDDDBDDBDDBLLLLLLLLLUBBUBBUBB";
+        assert_eq!((SOLVED * Move::R).to_facelet_str(), r);
+        assert_eq!(Cube::from_facelet_str(r), Ok(Move::R.to_cube()));
+    }
+
+    #[test]
+    fn round_trip() {
+        let scramble: Algorithm = "R U2 F' L B D R U F2 D' L2 B R' U' F D2 B' L R2 U2 F' M y"
+            .parse()
+            .unwrap();
+        for cube in [SUPERFLIP, scramble.to_cube()] {
+            assert_eq!(Cube::from_facelets(&cube.to_facelets()), Ok(cube.clone()));
+
+            let colors = cube.to_facelet_str_with(&ColorScheme::COLORS);
+            assert_eq!(
+                Cube::from_facelet_str_with(&colors, &ColorScheme::COLORS),
+                Ok(cube.clone())
+            );
+
+            // face letters only describe the state relative to the way the cube is held
+            assert_eq!(
+                Cube::from_facelet_str(&cube.to_facelet_str()),
+                Ok(cube.normalised())
+            );
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let solved = SOLVED.to_facelet_str();
+        assert_eq!(
+            Cube::from_facelet_str(&solved[1..]),
+            Err(FaceletError::Length(53).into())
+        );
+        assert_eq!(
+            Cube::from_facelet_str(&solved.replacen('U', "X", 1)),
+            Err(FaceletError::UnknownFacelet {
+                index: 0,
+                facelet: 'X'
+            }
+            .into())
+        );
+        assert_eq!(
+            Cube::from_facelet_str(&solved.replacen('U', "F", 1)),
+            Err(FaceletError::StickerCount {
+                color: Color::White,
+                count: 8
+            }
+            .into())
+        );
+
+        // UB edge with its U sticker swapped with the F sticker of UF
+        let mut facelets = SOLVED.to_facelets();
+        facelets.swap(1, 13);
+        assert!(matches!(
+            Cube::from_facelets(&facelets),
+            Err(CubeError::Facelets(FaceletError::Edge { position: 0, .. }))
+        ));
+        // twisting a corner the wrong way gives its mirror image
+        let mut facelets = SOLVED.to_facelets();
+        facelets.swap(0, 9);
+        assert!(matches!(
+            Cube::from_facelets(&facelets),
+            Err(CubeError::Facelets(FaceletError::Corner {
+                position: 0,
+                ..
+            }))
+        ));
+        // every piece exists but one edge is flipped
+        let mut facelets = SOLVED.to_facelets();
+        facelets.swap(1, 19);
+        assert_eq!(Cube::from_facelets(&facelets), Err(CubeError::FlippedEdge));
+    }
+}
@@ -1,13 +1,40 @@
+use std::iter::zip;
+use std::sync::Arc;
+
 use crate::cube::Cube;
+use crate::symmetry::Symmetry;
 
 pub trait Heuristic<T>: Copy {
+    /// what a search carries from a state to its neighbours, so bounds that are only stored
+    /// relative to a neighbour's, like mod 3 pruning tables, don't have to be worked out from
+    /// scratch. Bounds that are looked up directly just carry the bound
+    type Memo: Copy;
+
     fn lower_bound(self, state: &T) -> u8;
+
+    /// the memo of a state with no known neighbours
+    fn memo(self, state: &T) -> Self::Memo;
+
+    /// the memo of `state`, which is one move away from the state with memo `parent`
+    fn next_memo(self, state: &T, parent: Self::Memo) -> Self::Memo;
+
+    fn memo_bound(self, memo: Self::Memo) -> u8;
 }
 
 #[derive(Clone, Copy)]
 pub struct ZeroBound;
-impl Heuristic<Cube> for ZeroBound {
-    fn lower_bound(self, _state: &Cube) -> u8 {
+impl<T> Heuristic<T> for ZeroBound {
+    type Memo = ();
+
+    fn lower_bound(self, _state: &T) -> u8 {
+        0
+    }
+
+    fn memo(self, _state: &T) {}
+
+    fn next_memo(self, _state: &T, _parent: ()) {}
+
+    fn memo_bound(self, _memo: ()) -> u8 {
         0
     }
 }
@@ -15,17 +42,309 @@ impl Heuristic<Cube> for ZeroBound {
 #[derive(Clone, Copy)]
 pub struct EOBound;
 impl Heuristic<Cube> for EOBound {
+    type Memo = u8;
+
     fn lower_bound(self, state: &Cube) -> u8 {
         state.eo.iter().sum::<u8>() % 4
     }
+
+    fn memo(self, state: &Cube) -> u8 {
+        self.lower_bound(state)
+    }
+
+    fn next_memo(self, state: &Cube, _parent: u8) -> u8 {
+        self.lower_bound(state)
+    }
+
+    fn memo_bound(self, memo: u8) -> u8 {
+        memo
+    }
+}
+
+/// a half turn metric bound used in the slice turn metric, where a slice turn does the work of
+/// two face turns
+#[derive(Clone, Copy)]
+pub struct Halved<H>(pub H);
+impl<T, H: Heuristic<T>> Heuristic<T> for Halved<H> {
+    type Memo = H::Memo;
+
+    fn lower_bound(self, state: &T) -> u8 {
+        self.0.lower_bound(state).div_ceil(2)
+    }
+
+    fn memo(self, state: &T) -> H::Memo {
+        self.0.memo(state)
+    }
+
+    fn next_memo(self, state: &T, parent: H::Memo) -> H::Memo {
+        self.0.next_memo(state, parent)
+    }
+
+    fn memo_bound(self, memo: H::Memo) -> u8 {
+        self.0.memo_bound(memo).div_ceil(2)
+    }
+}
+
+/// the larger of the bounds for the state and for its inverse, which takes as many moves to
+/// solve. The inverse is one move away from the previous inverse, but that move is made before
+/// the others so its coordinates aren't one move apart and its memo is worked out from scratch
+#[derive(Clone, Copy)]
+pub struct Inverse<H>(pub H);
+impl<H: Heuristic<Cube>> Heuristic<Cube> for Inverse<H> {
+    type Memo = (H::Memo, H::Memo);
+
+    fn lower_bound(self, state: &Cube) -> u8 {
+        let inverse = self.0.lower_bound(&state.inverse());
+        self.0.lower_bound(state).max(inverse)
+    }
+
+    fn memo(self, state: &Cube) -> Self::Memo {
+        (self.0.memo(state), self.0.memo(&state.inverse()))
+    }
+
+    fn next_memo(self, state: &Cube, (parent, _): Self::Memo) -> Self::Memo {
+        (
+            self.0.next_memo(state, parent),
+            self.0.memo(&state.inverse()),
+        )
+    }
+
+    fn memo_bound(self, (m0, m1): Self::Memo) -> u8 {
+        self.0.memo_bound(m0).max(self.0.memo_bound(m1))
+    }
+}
+
+/// the bound of the state conjugated by a symmetry, which takes as many moves to solve, so a
+/// table for some of the pieces covers the ones the symmetry moves there. Conjugates of
+/// neighbouring states are neighbours, so memos carry over
+#[derive(Clone, Copy)]
+pub struct Conjugated<H>(pub H, pub Symmetry);
+impl<H: Heuristic<Cube>> Heuristic<Cube> for Conjugated<H> {
+    type Memo = H::Memo;
+
+    fn lower_bound(self, state: &Cube) -> u8 {
+        self.0.lower_bound(&state.conjugate(self.1))
+    }
+
+    fn memo(self, state: &Cube) -> H::Memo {
+        self.0.memo(&state.conjugate(self.1))
+    }
+
+    fn next_memo(self, state: &Cube, parent: H::Memo) -> H::Memo {
+        self.0.next_memo(&state.conjugate(self.1), parent)
+    }
+
+    fn memo_bound(self, memo: H::Memo) -> u8 {
+        self.0.memo_bound(memo)
+    }
+}
+
+/// the memo of a `DynHeuristic`. A table's memo is a byte, an `Inverse` packs its two in halves
+pub type DynMemo = u32;
+
+/// a heuristic picked at runtime, so which tables bound a search can be chosen without
+/// recompiling. Memos are carried like `Heuristic`'s, so mod 3 tables keep their exact costs
+pub trait DynHeuristic: Send + Sync {
+    fn lower_bound(&self, state: &Cube) -> u8;
+
+    fn memo(&self, state: &Cube) -> DynMemo;
+
+    fn next_memo(&self, state: &Cube, parent: DynMemo) -> DynMemo;
+
+    fn memo_bound(&self, memo: DynMemo) -> u8;
+}
+
+/// a table owned by a heuristic set, tables implement `Heuristic` through references so it's
+/// looked up through `&*self.0`
+pub struct Owned<T>(pub T);
+impl<T> DynHeuristic for Owned<T>
+where
+    T: std::ops::Deref + Send + Sync,
+    for<'a> &'a T::Target: Heuristic<Cube, Memo = u8>,
+{
+    fn lower_bound(&self, state: &Cube) -> u8 {
+        (&*self.0).lower_bound(state)
+    }
+
+    fn memo(&self, state: &Cube) -> DynMemo {
+        (&*self.0).memo(state).into()
+    }
+
+    fn next_memo(&self, state: &Cube, parent: DynMemo) -> DynMemo {
+        (&*self.0).next_memo(state, parent as u8).into()
+    }
+
+    fn memo_bound(&self, memo: DynMemo) -> u8 {
+        (&*self.0).memo_bound(memo as u8)
+    }
+}
+
+impl<H: DynHeuristic + ?Sized> DynHeuristic for Box<H> {
+    fn lower_bound(&self, state: &Cube) -> u8 {
+        (**self).lower_bound(state)
+    }
+
+    fn memo(&self, state: &Cube) -> DynMemo {
+        (**self).memo(state)
+    }
+
+    fn next_memo(&self, state: &Cube, parent: DynMemo) -> DynMemo {
+        (**self).next_memo(state, parent)
+    }
+
+    fn memo_bound(&self, memo: DynMemo) -> u8 {
+        (**self).memo_bound(memo)
+    }
+}
+
+impl<H: DynHeuristic + ?Sized> DynHeuristic for Arc<H> {
+    fn lower_bound(&self, state: &Cube) -> u8 {
+        (**self).lower_bound(state)
+    }
+
+    fn memo(&self, state: &Cube) -> DynMemo {
+        (**self).memo(state)
+    }
+
+    fn next_memo(&self, state: &Cube, parent: DynMemo) -> DynMemo {
+        (**self).next_memo(state, parent)
+    }
+
+    fn memo_bound(&self, memo: DynMemo) -> u8 {
+        (**self).memo_bound(memo)
+    }
+}
+
+impl<H: DynHeuristic> DynHeuristic for Halved<H> {
+    fn lower_bound(&self, state: &Cube) -> u8 {
+        self.0.lower_bound(state).div_ceil(2)
+    }
+
+    fn memo(&self, state: &Cube) -> DynMemo {
+        self.0.memo(state)
+    }
+
+    fn next_memo(&self, state: &Cube, parent: DynMemo) -> DynMemo {
+        self.0.next_memo(state, parent)
+    }
+
+    fn memo_bound(&self, memo: DynMemo) -> u8 {
+        self.0.memo_bound(memo).div_ceil(2)
+    }
+}
+
+/// packs the memos of a state and of its inverse into the low and high halves of one
+fn pack_inverse(memo: DynMemo, inverse: DynMemo) -> DynMemo {
+    assert!(
+        memo <= 0xffff && inverse <= 0xffff,
+        "an inverted heuristic's memos have to fit in 16 bits"
+    );
+    memo | inverse << 16
+}
+
+/// the memos of the state and of its inverse go in the low and high halves, so the inverted
+/// heuristic's memos have to fit in 16 bits
+impl<H: DynHeuristic> DynHeuristic for Inverse<H> {
+    fn lower_bound(&self, state: &Cube) -> u8 {
+        let inverse = self.0.lower_bound(&state.inverse());
+        self.0.lower_bound(state).max(inverse)
+    }
+
+    fn memo(&self, state: &Cube) -> DynMemo {
+        pack_inverse(self.0.memo(state), self.0.memo(&state.inverse()))
+    }
+
+    /// the inverse's memo is found from scratch, a move on the state isn't one move on its inverse
+    fn next_memo(&self, state: &Cube, parent: DynMemo) -> DynMemo {
+        let memo = self.0.next_memo(state, parent & 0xffff);
+        pack_inverse(memo, self.0.memo(&state.inverse()))
+    }
+
+    fn memo_bound(&self, memo: DynMemo) -> u8 {
+        let inverse = self.0.memo_bound(memo >> 16);
+        self.0.memo_bound(memo & 0xffff).max(inverse)
+    }
+}
+
+impl<H: DynHeuristic> DynHeuristic for Conjugated<H> {
+    fn lower_bound(&self, state: &Cube) -> u8 {
+        self.0.lower_bound(&state.conjugate(self.1))
+    }
+
+    fn memo(&self, state: &Cube) -> DynMemo {
+        self.0.memo(&state.conjugate(self.1))
+    }
+
+    fn next_memo(&self, state: &Cube, parent: DynMemo) -> DynMemo {
+        self.0.next_memo(&state.conjugate(self.1), parent)
+    }
+
+    fn memo_bound(&self, memo: DynMemo) -> u8 {
+        self.0.memo_bound(memo)
+    }
+}
+
+/// the most heuristics a set can have, its memo has room for each one's
+pub const MAX_HEURISTICS: usize = 16;
+
+/// the largest bound of a list of at most `MAX_HEURISTICS` heuristics picked at runtime
+#[derive(Clone, Copy)]
+pub struct HeuristicSet<'a>(&'a [Box<dyn DynHeuristic>]);
+
+impl<'a> HeuristicSet<'a> {
+    /// panics with more than `MAX_HEURISTICS`, which `load_heuristics` and `SolverBuilder::build`
+    /// report as errors instead. Checked once here so the memos don't have to be at every node
+    pub fn new(heuristics: &'a [Box<dyn DynHeuristic>]) -> Self {
+        assert!(
+            heuristics.len() <= MAX_HEURISTICS,
+            "a heuristic set can have at most {MAX_HEURISTICS} heuristics"
+        );
+        HeuristicSet(heuristics)
+    }
+}
+
+impl Heuristic<Cube> for HeuristicSet<'_> {
+    type Memo = [DynMemo; MAX_HEURISTICS];
+
+    fn lower_bound(self, state: &Cube) -> u8 {
+        self.0
+            .iter()
+            .map(|h| h.lower_bound(state))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn memo(self, state: &Cube) -> Self::Memo {
+        let mut memo = [0; MAX_HEURISTICS];
+        for (memo, h) in zip(&mut memo, self.0) {
+            *memo = h.memo(state);
+        }
+        memo
+    }
+
+    fn next_memo(self, state: &Cube, mut parent: Self::Memo) -> Self::Memo {
+        for (memo, h) in zip(&mut parent, self.0) {
+            *memo = h.next_memo(state, *memo);
+        }
+        parent
+    }
+
+    fn memo_bound(self, memo: Self::Memo) -> u8 {
+        zip(memo, self.0)
+            .map(|(memo, h)| h.memo_bound(memo))
+            .max()
+            .unwrap_or(0)
+    }
 }
 
 #[rustfmt::skip]
-impl<T, H0, H1> Heuristic<T> for (H0, H1) 
-where 
+impl<T, H0, H1> Heuristic<T> for (H0, H1)
+where
     H0: Heuristic<T>,
     H1: Heuristic<T>,
 {
+    type Memo = (H0::Memo, H1::Memo);
+
     fn lower_bound(self, state: &T) -> u8 {
         let (h0, h1) = self;
         [
@@ -33,15 +352,35 @@ where
             h1.lower_bound(state),
         ].into_iter().max().unwrap()
     }
+
+    fn memo(self, state: &T) -> Self::Memo {
+        let (h0, h1) = self;
+        (h0.memo(state), h1.memo(state))
+    }
+
+    fn next_memo(self, state: &T, (m0, m1): Self::Memo) -> Self::Memo {
+        let (h0, h1) = self;
+        (h0.next_memo(state, m0), h1.next_memo(state, m1))
+    }
+
+    fn memo_bound(self, (m0, m1): Self::Memo) -> u8 {
+        let (h0, h1) = self;
+        [
+            h0.memo_bound(m0),
+            h1.memo_bound(m1),
+        ].into_iter().max().unwrap()
+    }
 }
 
 #[rustfmt::skip]
-impl<T, H0, H1, H2> Heuristic<T> for (H0, H1, H2) 
-where 
+impl<T, H0, H1, H2> Heuristic<T> for (H0, H1, H2)
+where
     H0: Heuristic<T>,
     H1: Heuristic<T>,
     H2: Heuristic<T>,
 {
+    type Memo = (H0::Memo, H1::Memo, H2::Memo);
+
     fn lower_bound(self, state: &T) -> u8 {
         let (h0, h1, h2) = self;
         [
@@ -50,16 +389,41 @@ where
             h2.lower_bound(state),
         ].into_iter().max().unwrap()
     }
+
+    fn memo(self, state: &T) -> Self::Memo {
+        let (h0, h1, h2) = self;
+        (h0.memo(state), h1.memo(state), h2.memo(state))
+    }
+
+    fn next_memo(self, state: &T, (m0, m1, m2): Self::Memo) -> Self::Memo {
+        let (h0, h1, h2) = self;
+        (
+            h0.next_memo(state, m0),
+            h1.next_memo(state, m1),
+            h2.next_memo(state, m2),
+        )
+    }
+
+    fn memo_bound(self, (m0, m1, m2): Self::Memo) -> u8 {
+        let (h0, h1, h2) = self;
+        [
+            h0.memo_bound(m0),
+            h1.memo_bound(m1),
+            h2.memo_bound(m2),
+        ].into_iter().max().unwrap()
+    }
 }
 
 #[rustfmt::skip]
-impl<T, H0, H1, H2, H3> Heuristic<T> for (H0, H1, H2, H3) 
-where 
+impl<T, H0, H1, H2, H3> Heuristic<T> for (H0, H1, H2, H3)
+where
     H0: Heuristic<T>,
     H1: Heuristic<T>,
     H2: Heuristic<T>,
     H3: Heuristic<T>,
 {
+    type Memo = (H0::Memo, H1::Memo, H2::Memo, H3::Memo);
+
     fn lower_bound(self, state: &T) -> u8 {
         let (h0, h1, h2, h3) = self;
         [
@@ -69,4 +433,140 @@ where
             h3.lower_bound(state),
         ].into_iter().max().unwrap()
     }
+
+    fn memo(self, state: &T) -> Self::Memo {
+        let (h0, h1, h2, h3) = self;
+        (h0.memo(state), h1.memo(state), h2.memo(state), h3.memo(state))
+    }
+
+    fn next_memo(self, state: &T, (m0, m1, m2, m3): Self::Memo) -> Self::Memo {
+        let (h0, h1, h2, h3) = self;
+        (
+            h0.next_memo(state, m0),
+            h1.next_memo(state, m1),
+            h2.next_memo(state, m2),
+            h3.next_memo(state, m3),
+        )
+    }
+
+    fn memo_bound(self, (m0, m1, m2, m3): Self::Memo) -> u8 {
+        let (h0, h1, h2, h3) = self;
+        [
+            h0.memo_bound(m0),
+            h1.memo_bound(m1),
+            h2.memo_bound(m2),
+            h3.memo_bound(m3),
+        ].into_iter().max().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::SOLVED;
+    use crate::mv::Metric;
+    use crate::mv::Move::*;
+    use crate::packed_table::{Mod3PruningTable, PackedTable};
+    use crate::pruning_table::{CornerOrientation, EO, PruningTable};
+
+    #[test]
+    fn heuristic_set_matches_tuple() {
+        let co = PruningTable::<Cube, CornerOrientation>::generate();
+        let eo = PruningTable::<Cube, EO>::generate();
+        let heuristics: Vec<Box<dyn DynHeuristic>> = vec![
+            Box::new(Owned(PruningTable::<Cube, CornerOrientation>::generate())),
+            Box::new(Halved(Owned(PruningTable::<Cube, EO>::generate()))),
+        ];
+        let set = HeuristicSet::new(&heuristics);
+        let tuple = (co.as_ref(), Halved(eo.as_ref()));
+
+        let mut cube = SOLVED;
+        for mv in [R, U, F, L2, B3, D, F, R3] {
+            cube = cube * mv;
+            assert_eq!(set.lower_bound(&cube), tuple.lower_bound(&cube));
+        }
+        assert_eq!(HeuristicSet::new(&[]).lower_bound(&cube), 0);
+    }
+
+    #[test]
+    fn heuristic_set_memos() {
+        let eo = PruningTable::<Cube, EO>::generate();
+        let mod3 = || Box::new(Mod3PruningTable::pack(&eo, Metric::Htm));
+        let heuristics: Vec<Box<dyn DynHeuristic>> = vec![
+            Box::new(Owned(mod3())),
+            Box::new(Inverse(Owned(mod3()))),
+            Box::new(Conjugated(Owned(mod3()), Symmetry::ALL[9])),
+        ];
+        let set = HeuristicSet::new(&heuristics);
+        let tuple = (
+            eo.as_ref(),
+            Inverse(eo.as_ref()),
+            Conjugated(eo.as_ref(), Symmetry::ALL[9]),
+        );
+
+        let mut cube = SOLVED;
+        let mut memo = set.memo(&cube);
+        for mv in [F, R, B3, U, L2, F, D, B, R3, F2] {
+            cube = cube * mv;
+            memo = set.next_memo(&cube, memo);
+            // the exact costs are carried, not worked out from scratch
+            assert_eq!(
+                &memo[..3],
+                [
+                    heuristics[0].memo(&cube),
+                    heuristics[1].memo(&cube),
+                    heuristics[2].memo(&cube),
+                ]
+            );
+            assert_eq!(set.memo_bound(memo), tuple.lower_bound(&cube));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "at most 16 heuristics")]
+    fn heuristic_set_limit() {
+        let eo: Arc<dyn DynHeuristic> = Arc::new(Owned(PruningTable::<Cube, EO>::generate()));
+        let heuristics: Vec<Box<dyn DynHeuristic>> = (0..=MAX_HEURISTICS)
+            .map(|_| Box::new(eo.clone()) as Box<dyn DynHeuristic>)
+            .collect();
+        HeuristicSet::new(&heuristics);
+    }
+
+    #[test]
+    #[should_panic(expected = "16 bits")]
+    fn inverse_memos_fit() {
+        // an inverse's memos take 32 bits, too many to be packed into another's
+        let eo = Owned(PruningTable::<Cube, EO>::generate());
+        Inverse(Inverse(eo)).memo(&F.to_cube());
+    }
+
+    #[test]
+    fn inverse_and_conjugated_bounds() {
+        let co = PruningTable::<Cube, CornerOrientation>::generate();
+        let co = co.as_ref();
+        let sym = Symmetry::ALL[9];
+
+        let mut cube = SOLVED;
+        let (mut inverse_memo, mut conjugated_memo) =
+            (Inverse(co).memo(&cube), Conjugated(co, sym).memo(&cube));
+        for (length, mv) in [R, U, F, L2, B3, D, F, R3].into_iter().enumerate() {
+            cube = cube * mv;
+            let bound = Inverse(co).lower_bound(&cube);
+            assert_eq!(
+                bound,
+                co.lower_bound(&cube).max(co.lower_bound(&cube.inverse()))
+            );
+            assert!(bound as usize <= length + 1);
+
+            inverse_memo = Inverse(co).next_memo(&cube, inverse_memo);
+            assert_eq!(Inverse(co).memo_bound(inverse_memo), bound);
+
+            let conjugated = Conjugated(co, sym).lower_bound(&cube);
+            assert_eq!(conjugated, co.lower_bound(&cube.conjugate(sym)));
+            assert!(conjugated as usize <= length + 1);
+
+            conjugated_memo = Conjugated(co, sym).next_memo(&cube, conjugated_memo);
+            assert_eq!(Conjugated(co, sym).memo_bound(conjugated_memo), conjugated);
+        }
+    }
 }
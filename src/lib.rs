@@ -0,0 +1,32 @@
+#![feature(generic_const_exprs)]
+
+pub mod algorithm;
+pub mod batch;
+pub mod cube;
+pub mod facelets;
+pub mod heuristics;
+pub mod mapped_table;
+pub mod move_table;
+pub mod mv;
+pub mod packed_table;
+pub mod parallel;
+pub mod progress;
+pub mod pruning_table;
+pub mod puzzle;
+pub mod random;
+pub mod search;
+pub mod solver;
+pub mod symmetry;
+pub mod table_file;
+pub mod tables;
+pub mod thistlethwaite;
+pub mod two_phase;
+
+pub use algorithm::Algorithm;
+pub use cube::{Cube, CubeError};
+pub use heuristics::{DynHeuristic, Heuristic};
+pub use mv::{Metric, Move};
+pub use packed_table::Encoding;
+pub use pruning_table::{Coordinate, PruningTable};
+pub use puzzle::Puzzle;
+pub use solver::{SolveResult, Solver, SolverBuilder, SolverError};
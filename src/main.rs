@@ -1,82 +1,1386 @@
-#![feature(stmt_expr_attributes)]
 #![feature(generic_const_exprs)]
 
-mod cube;
-mod heuristics;
-mod mv;
-mod pruning_table;
-mod puzzle;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use cube::*;
-use mv::Move::*;
-use pruning_table::*;
+use cube_solver::algorithm::Algorithm;
+use cube_solver::batch::{BatchResult, BatchSummary};
+use cube_solver::cube::*;
+use cube_solver::heuristics::Heuristic;
+use cube_solver::move_table::Pair;
+use cube_solver::mv::Metric;
+use cube_solver::packed_table::Encoding;
+use cube_solver::progress::{progress, with_progress};
+use cube_solver::pruning_table::*;
+use cube_solver::puzzle::Puzzle;
+use cube_solver::random::{self, Rng};
+use cube_solver::search::{Observer, SearchEvent};
+use cube_solver::solver::{SolveResult, Solver, SolverError};
+use cube_solver::table_file::{read_header, read_table};
+use cube_solver::tables::*;
+use cube_solver::two_phase::{Phase1Cube, Phase2Cube, two_phase_counted};
+use cube_solver::{thistlethwaite, two_phase};
 
-use puzzle::Puzzle;
+const USAGE: &str = "\
+usage: cube-solver <command> [options]
+
+commands:
+  solve <scramble>        solve the state a scramble leaves, or a 54 character facelet string
+  solve --facelets <str>  solve a URFDLB facelet string
+  scramble                print scrambles of uniformly random states
+  tables generate|verify|info [table...]
+                          generate tables that are missing or damaged, check every table's
+                          checksum, or print what each table's header says
+  show <scramble>         print the net of a scramble, or of --facelets <str>
+  bench [scramble...]     solve a fixed set of scrambles, or the ones given, and report the speed.
+                          With --two-phase, compare Kociemba's search on coordinates and on cubes
+  batch <file>            solve the scrambles or facelet strings in a file, one per line, or
+                          from stdin for -, and summarise the solutions and search speed
+  help                    print this message
+
+options:
+  --metric=htm|qtm|stm    the metric solutions are optimal in, htm by default
+  --max-depth=<n>         the longest solution searched for, or the length two phase solutions
+                          and scrambles are shortened to
+  --time-limit=<seconds>  give up on a solve after this long
+  --max-nodes=<n>         give up on a solve after searching this many nodes
+  --tables=<list>         the tables that bound the search, like corner,first6edges@inverse
+  --tables-file=<path>    the same, listed in a file
+  --table-dir=<path>      where tables are read from and written to, . by default
+  --encoding=byte|nibble|mod3
+  --mmap                  map byte tables instead of reading them
+  --symmetry              use the symmetry reduced corner table
+  --threads=<n>
+  --two-phase             solve with Kociemba's algorithm instead of optimally
+  --thistlethwaite        solve with Thistlethwaite's algorithm instead of optimally
+  --all[=<k>]             list every optimal solution, and those up to k moves longer
+  --count=<n>             the number of scrambles to print, 1 by default
+  --seed=<n>              the seed of the random states, to make the same scrambles again
+  --format=plain|json     how results are printed
+  --output=<path>         where batch writes each scramble's result, as CSV or JSON depending
+                          on the extension, with only the summary printed
+  --jobs=<n>              how many scrambles batch solves at once, sharing the tables
+  -q, --quiet             only print results and errors
+  -v, --verbose           also print the cube being solved, and every second how far a solve has
+                          got
+
+exit codes:
+  0  success
+  1  no solution within the depth, time or node limit
+  2  bad arguments
+  3  an invalid scramble, facelet string or cube
+  4  a table that can't be used or failed to verify
+";
+
+const COMMANDS: [&str; 7] = [
+    "solve", "scramble", "tables", "show", "bench", "batch", "help",
+];
+
+/// options followed by a value, as `--name=value` or `--name value`
+const VALUE_OPTIONS: [&str; 16] = [
+    "--facelets",
+    "--metric",
+    "--max-depth",
+    "--time-limit",
+    "--max-nodes",
+    "--tables",
+    "--tables-file",
+    "--table-dir",
+    "--encoding",
+    "--threads",
+    "--count",
+    "--seed",
+    "--jobs",
+    "--output",
+    "--format",
+    "--all",
+];
+
+const FLAGS: [&str; 10] = [
+    "--mmap",
+    "--symmetry",
+    "--two-phase",
+    "--thistlethwaite",
+    "--all",
+    "--quiet",
+    "-q",
+    "--verbose",
+    "-v",
+    "--help",
+];
+
+/// the scrambles `bench` solves by default, optimal in 7 to 12 moves
+const BENCH_SCRAMBLES: [&str; 5] = [
+    "R U2 F L B D R",
+    "F' L D2 B R' U F2 L' D",
+    "U R2 F B R B2 R U2 L B2 R U'",
+    "R U F D2 L B' U R2 F D' L2 B",
+    "L2 D' B U2 F' R D L' B2 U R' F",
+];
+
+/// why a command failed, each with its own exit code
+enum Failure {
+    NoSolution(String),
+    Usage(String),
+    Invalid(String),
+    Tables(String),
+}
+
+impl Failure {
+    fn code(&self) -> i32 {
+        match self {
+            Failure::NoSolution(_) => 1,
+            Failure::Usage(_) => 2,
+            Failure::Invalid(_) => 3,
+            Failure::Tables(_) => 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Plain,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+/// the command line, split into the command, its arguments and the options
+struct Args {
+    command: &'static str,
+    positional: Vec<String>,
+    values: HashMap<&'static str, String>,
+    flags: HashSet<&'static str>,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Self, Failure> {
+        let mut positional = Vec::new();
+        let mut values = HashMap::new();
+        let mut flags = HashSet::new();
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            // a lone - is a file name meaning stdin
+            if !arg.starts_with('-') || arg == "-" {
+                positional.push(arg.clone());
+                continue;
+            }
+
+            let (name, value) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (arg.as_str(), None),
+            };
+            if let Some(&flag) = FLAGS.iter().find(|&&flag| flag == name)
+                && value.is_none()
+            {
+                flags.insert(flag);
+            } else if let Some(&option) = VALUE_OPTIONS.iter().find(|&&option| option == name) {
+                let value = match value {
+                    Some(value) => value,
+                    None => args
+                        .next()
+                        .cloned()
+                        .ok_or_else(|| Failure::Usage(format!("{name} needs a value")))?,
+                };
+                values.insert(option, value);
+            } else {
+                return Err(Failure::Usage(format!("Unknown option {arg}")));
+            }
+        }
+
+        // a scramble on its own is solved, like before there were commands
+        let command = match COMMANDS
+            .iter()
+            .find(|&&command| positional.first().is_some_and(|first| first == command))
+        {
+            Some(&command) => {
+                positional.remove(0);
+                command
+            }
+            None if positional.is_empty() && !flags.contains("--help") => {
+                return Err(Failure::Usage("No command given".to_string()));
+            }
+            None => "solve",
+        };
+        let command = match flags.contains("--help") {
+            true => "help",
+            false => command,
+        };
+
+        Ok(Args {
+            command,
+            positional,
+            values,
+            flags,
+        })
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.contains(name)
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    fn parsed<T: std::str::FromStr>(&self, name: &str) -> Result<Option<T>, Failure>
+    where
+        T::Err: std::fmt::Display,
+    {
+        self.value(name)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|e| Failure::Usage(format!("Invalid {name} {value}: {e}")))
+            })
+            .transpose()
+    }
+}
+
+/// the options every command shares
+struct Settings {
+    metric: Metric,
+    max_depth: Option<u8>,
+    time_limit: Option<Duration>,
+    max_nodes: Option<u64>,
+    /// the heuristic set from `--tables` or `--tables-file`, Korf's tables otherwise
+    tables: Option<Vec<String>>,
+    options: TableOptions,
+    threads: usize,
+    format: Format,
+    verbosity: Verbosity,
+}
+
+impl Settings {
+    fn new(args: &Args) -> Result<Self, Failure> {
+        let tables = match (args.value("--tables"), args.value("--tables-file")) {
+            (Some(list), _) => Some(parse_table_names(list)),
+            (None, Some(path)) => match std::fs::read_to_string(path) {
+                Ok(file) => Some(parse_table_names(&file)),
+                Err(e) => return Err(Failure::Usage(format!("Can't read {path}: {e}"))),
+            },
+            (None, None) => None,
+        };
+        let time_limit = match args.parsed::<f64>("--time-limit")? {
+            Some(seconds) if seconds.is_finite() && seconds > 0.0 => {
+                Some(Duration::from_secs_f64(seconds))
+            }
+            Some(seconds) => {
+                return Err(Failure::Usage(format!("Invalid --time-limit {seconds}")));
+            }
+            None => None,
+        };
+        let format = match args.value("--format") {
+            None | Some("plain") => Format::Plain,
+            Some("json") => Format::Json,
+            Some(format) => {
+                return Err(Failure::Usage(format!(
+                    "Unknown format {format}, expected plain or json"
+                )));
+            }
+        };
+        let verbosity = match (
+            args.flag("--quiet") || args.flag("-q"),
+            args.flag("--verbose") || args.flag("-v"),
+        ) {
+            (true, true) => {
+                return Err(Failure::Usage(
+                    "--quiet and --verbose can't be used together".to_string(),
+                ));
+            }
+            (true, false) => Verbosity::Quiet,
+            (false, true) => Verbosity::Verbose,
+            (false, false) => Verbosity::Normal,
+        };
+
+        Ok(Settings {
+            metric: args.parsed("--metric")?.unwrap_or(Metric::Htm),
+            max_depth: args.parsed("--max-depth")?,
+            time_limit,
+            max_nodes: args.parsed("--max-nodes")?,
+            tables,
+            options: TableOptions {
+                dir: args
+                    .value("--table-dir")
+                    .map_or_else(|| PathBuf::from("."), PathBuf::from),
+                encoding: args.parsed("--encoding")?.unwrap_or(Encoding::Byte),
+                // maps the tables instead of reading them, startup is instant and processes share
+                // the pages
+                mmap: args.flag("--mmap"),
+                // a corner table reduced by the 16 symmetries keeping U and D, about 16 times smaller
+                symmetry: args.flag("--symmetry"),
+            },
+            threads: args
+                .parsed::<usize>("--threads")?
+                .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()))
+                .max(1),
+            format,
+            verbosity,
+        })
+    }
+
+    /// the listed heuristic set, or Korf's tables
+    fn table_names(&self) -> Vec<String> {
+        self.tables
+            .clone()
+            .unwrap_or_else(|| DEFAULT_TABLES.map(str::to_string).to_vec())
+    }
+}
 
 fn main() {
-    dbg!(PartialEdges::<0, 6>::MAX);
-    let scramble = R * U2 * F * L * B * D * R;
-    //let scramble = SUPERFLIP;
-
-    scramble.print_net();
-
-    eprintln!("Loading first 6 edges pruning table...");
-    let start = std::time::Instant::now();
-    let first6edges_pruning_table: Box<PruningTable<Cube, PartialEdges<0, 6>>> =
-        load_pruning_table("first6edges_pruning_table.bin");
-    eprintln!(
-        "Loaded first 6 edges pruning table in {:?}",
-        start.elapsed()
-    );
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() {
+        eprint!("{USAGE}");
+        std::process::exit(Failure::Usage(String::new()).code());
+    }
+
+    let result = Args::parse(&args).and_then(|args| {
+        let settings = Settings::new(&args)?;
+        if settings.verbosity == Verbosity::Quiet {
+            with_progress(Arc::new(|_| {}), || run(&args, &settings))
+        } else {
+            run(&args, &settings)
+        }
+    });
+
+    if let Err(failure) = result {
+        match &failure {
+            Failure::Usage(e) => eprintln!("{e}, see cube-solver help"),
+            Failure::NoSolution(e) | Failure::Invalid(e) | Failure::Tables(e) => eprintln!("{e}"),
+        }
+        std::process::exit(failure.code());
+    }
+}
+
+fn run(args: &Args, settings: &Settings) -> Result<(), Failure> {
+    match args.command {
+        "solve" => solve(args, settings),
+        "scramble" => scramble(args, settings),
+        "tables" => tables(args, settings),
+        "show" => show(args, settings),
+        "bench" => bench(args, settings),
+        "batch" => batch(args, settings),
+        "help" => {
+            print!("{USAGE}");
+            Ok(())
+        }
+        _ => unreachable!("{} is in COMMANDS", args.command),
+    }
+}
+
+/// the cube given by `--facelets`, or by the arguments, which are joined so a scramble doesn't
+/// have to be quoted
+fn read_cube(args: &Args) -> Result<Cube, Failure> {
+    let cube = match (args.value("--facelets"), args.positional.as_slice()) {
+        (Some(facelets), []) => Cube::from_facelet_str(facelets)
+            .map_err(|e| Failure::Invalid(format!("Error parsing facelets: {e}")))?
+            .normalised(),
+        (None, []) => return Err(Failure::Usage("No scramble given".to_string())),
+        (None, scramble) => read_scramble(&scramble.join(" "))?,
+        (Some(_), _) => {
+            return Err(Failure::Usage(
+                "Expected a scramble or --facelets, not both".to_string(),
+            ));
+        }
+    };
+
+    // an unsolvable state would leave the search running forever
+    cube.validate()
+        .map_err(|e| Failure::Invalid(format!("Invalid cube: {e}")))?;
+    Ok(cube)
+}
+
+/// rotations, slices and the facelets of a cube held another way move the centers, so the cube is
+/// solved relative to the way it ends up held
+fn read_scramble(scramble: &str) -> Result<Cube, Failure> {
+    let cube = match scramble.parse::<Algorithm>() {
+        Ok(scramble) => Ok(scramble.to_cube()),
+        // 54 characters that aren't an algorithm are read as a URFDLB facelet string
+        Err(_) if scramble.len() == 54 => Cube::from_facelet_str(scramble)
+            .map_err(|e| Failure::Invalid(format!("Error parsing facelets: {e}"))),
+        Err(e) => Err(Failure::Invalid(format!("Error parsing scramble: {e}"))),
+    };
+    cube.map(|cube| cube.normalised())
+}
+
+fn solve(args: &Args, settings: &Settings) -> Result<(), Failure> {
+    let cube = read_cube(args)?;
+    if settings.verbosity == Verbosity::Verbose && settings.format == Format::Plain {
+        cube.print_net();
+    }
 
-    eprintln!("Loading last 6 edges pruning table...");
-    let start = std::time::Instant::now();
-    let last6edges_pruning_table: Box<PruningTable<Cube, PartialEdges<6, 12>>> =
-        load_pruning_table("last6edges_pruning_table.bin");
-    eprintln!("Loaded last 6 edges pruning table in {:?}", start.elapsed());
+    if args.flag("--two-phase") {
+        return solve_two_phase(settings, &cube);
+    }
+    if args.flag("--thistlethwaite") {
+        return solve_thistlethwaite(settings, &cube);
+    }
+
+    // --all lists every optimal solution, --all=k also those up to k moves longer
+    let all_solutions = match args.parsed::<u8>("--all")? {
+        Some(extra) => Some(extra),
+        None => args.flag("--all").then_some(0),
+    };
+    if all_solutions.is_some() && settings.metric != Metric::Htm {
+        return Err(Failure::Usage(
+            "Listing every solution is only supported in the half turn metric".to_string(),
+        ));
+    }
+
+    let solver = build_solver(settings)?;
+    if let Some(extra) = all_solutions {
+        list_solutions(settings, cube, extra, solver.heuristic());
+        return Ok(());
+    }
+
+    let result = solve_optimal(&solver, &cube)?;
+    let metric = settings.metric;
+    match settings.format {
+        Format::Plain => println!(
+            "Solution Found ({} {metric:?}): {}",
+            result.depth, result.solution
+        ),
+        Format::Json => println!("{}", result_to_json(&result, Json::new(), metric).finish()),
+    }
+    Ok(())
+}
+
+/// loads the tables the options list, Korf's by default, into a solver with the options' limits
+fn build_solver(settings: &Settings) -> Result<Solver, Failure> {
+    // names that don't parse are reported when the tables are loaded
+    let lookups: Vec<Lookup> = settings
+        .table_names()
+        .iter()
+        .filter_map(|name| parse_table(name).ok())
+        .map(|(_, lookup)| lookup)
+        .collect();
+    settings
+        .options
+        .check(settings.metric, &lookups)
+        .map_err(|e| Failure::Usage(e.to_string()))?;
+
+    let mut builder = Solver::builder()
+        .tables(settings.table_names())
+        .table_dir(&settings.options.dir)
+        .encoding(settings.options.encoding)
+        .mmap(settings.options.mmap)
+        .symmetry(settings.options.symmetry)
+        .metric(settings.metric)
+        .threads(settings.threads);
+    if let Some(max_depth) = settings.max_depth {
+        builder = builder.max_depth(max_depth);
+    }
+    if let Some(time_limit) = settings.time_limit {
+        builder = builder.time_limit(time_limit);
+    }
+    if let Some(max_nodes) = settings.max_nodes {
+        builder = builder.max_nodes(max_nodes);
+    }
+    // the solver drops its diagnostics without a callback
+    if settings.verbosity != Verbosity::Quiet {
+        builder = builder.progress(|line| eprintln!("{line}"));
+    }
+    if settings.verbosity == Verbosity::Verbose {
+        builder = builder.observer(Observer::new(heartbeat));
+    }
+
+    builder.build().map_err(solver_failure)
+}
+
+fn solver_failure(e: SolverError) -> Failure {
+    match e {
+        SolverError::Tables(e) => Failure::Tables(e),
+        SolverError::InvalidCube(e) => Failure::Invalid(format!("Invalid cube: {e}")),
+        SolverError::NoSolution { max_depth } => {
+            Failure::NoSolution(format!("No solution of up to {max_depth} moves"))
+        }
+        SolverError::Aborted {
+            reason,
+            nodes,
+            lower_bound,
+        } => Failure::NoSolution(format!(
+            "The search {reason} after {nodes} nodes, every solution is at least {lower_bound} moves"
+        )),
+    }
+}
+
+fn solve_optimal(solver: &Solver, cube: &Cube) -> Result<SolveResult, Failure> {
+    let result = solver.solve(cube);
+    if let Ok(result) = &result {
+        progress!("Elapsed: {:?}", result.elapsed);
+    }
+    result.map_err(solver_failure)
+}
+
+/// adds the fields of an optimal solution to `json`
+fn result_to_json(result: &SolveResult, json: Json, metric: Metric) -> Json {
+    json.string("solution", &result.solution.to_string())
+        .number("length", result.depth)
+        .string("metric", &format!("{metric:?}").to_lowercase())
+        .number("nodes", result.nodes)
+        .number("seconds", result.elapsed.as_secs_f64())
+}
+
+/// a line for each heartbeat of a long solve
+fn heartbeat(event: &SearchEvent) {
+    if let SearchEvent::Heartbeat {
+        nodes,
+        elapsed,
+        current_path_prefix,
+    } = event
+    {
+        progress!(
+            "searched {nodes} nodes in {elapsed:.2?}, at {}",
+            Algorithm::from(current_path_prefix.clone())
+        );
+    }
+}
+
+/// every optimal solution, and those up to `extra` moves longer
+fn list_solutions(settings: &Settings, scramble: Cube, extra: u8, heuristic: impl Heuristic<Cube>) {
+    let start = Instant::now();
+    let max_depth = settings.max_depth.unwrap_or(20);
+    let solutions = ida_all(scramble, max_depth, extra, 1000, heuristic);
+    progress!("Elapsed: {:?}", start.elapsed());
+
+    let solutions: Vec<String> = solutions
+        .into_iter()
+        .map(|path| Algorithm::from(path).to_string())
+        .collect();
+    match settings.format {
+        Format::Plain => {
+            println!("{} Solutions Found:", solutions.len());
+            for solution in solutions {
+                println!("{solution}");
+            }
+        }
+        Format::Json => {
+            let solutions: Vec<String> = solutions.iter().map(|s| json_string(s)).collect();
+            println!(
+                "{}",
+                Json::new()
+                    .raw("solutions", &format!("[{}]", solutions.join(",")))
+                    .number("seconds", start.elapsed().as_secs_f64())
+                    .finish()
+            );
+        }
+    }
+}
+
+/// Kociemba's tables, bounding the distance to the domino subgroup and to solved within it
+struct TwoPhaseTables {
+    co_slice: Box<PruningTable<Cube, (CornerOrientation, UDSlice)>>,
+    eo_slice: Box<PruningTable<Cube, (EO, UDSlice)>>,
+    cp_slice: Box<PruningTable<Cube, (CornerPermutation, SlicePermutation)>>,
+    ep_slice: Box<PruningTable<Cube, (UDEdgePermutation, SlicePermutation)>>,
+}
+
+impl TwoPhaseTables {
+    fn load(dir: &Path) -> Self {
+        progress!("Loading two phase pruning tables...");
+        let start = Instant::now();
+        let tables = TwoPhaseTables {
+            co_slice: load_pruning_table(dir, "co_slice_pruning_table", Metric::Htm),
+            eo_slice: load_pruning_table(dir, "eo_slice_pruning_table", Metric::Htm),
+            cp_slice: load_pruning_table(dir, "cp_slice_pruning_table", Metric::Htm),
+            ep_slice: load_pruning_table(dir, "ep_slice_pruning_table", Metric::Htm),
+        };
+        progress!("Loaded two phase pruning tables in {:?}", start.elapsed());
+        tables
+    }
+
+    fn phase1(&self) -> impl Heuristic<Phase1Cube> + '_ {
+        (Pair(self.co_slice.as_ref()), Pair(self.eo_slice.as_ref()))
+    }
 
-    eprintln!("Loading Corner pruning table...");
-    let start = std::time::Instant::now();
-    let corner_pruning_table: Box<PruningTable<Cube, (CornerOrientation, CornerPermutation)>> =
-        load_pruning_table("corner_pruning_table.bin");
-    eprintln!("Loaded corner pruning table in {:?}", start.elapsed());
+    fn phase2(&self) -> impl Heuristic<Phase2Cube> + '_ {
+        (Pair(self.cp_slice.as_ref()), Pair(self.ep_slice.as_ref()))
+    }
 
-    let start = std::time::Instant::now();
+    /// the same bounds looked up on the whole cube, which `bench` compares the speed of
+    fn cube_phase1(&self) -> impl Heuristic<Cube> + '_ {
+        (self.co_slice.as_ref(), self.eo_slice.as_ref())
+    }
 
-    if let Some(path) = ida(
+    fn cube_phase2(&self) -> impl Heuristic<Cube> + '_ {
+        (self.cp_slice.as_ref(), self.ep_slice.as_ref())
+    }
+}
+
+fn solve_two_phase(settings: &Settings, scramble: &Cube) -> Result<(), Failure> {
+    let tables = TwoPhaseTables::load(&settings.options.dir);
+
+    let start = Instant::now();
+    // a shorter solution is searched for until one of the target length is found
+    let target = settings.max_depth.unwrap_or(20);
+    let path = two_phase::two_phase(
         scramble,
-        20,
-        (
-            corner_pruning_table.as_ref(),
-            first6edges_pruning_table.as_ref(),
-            last6edges_pruning_table.as_ref(),
+        target,
+        settings.time_limit.unwrap_or(Duration::from_secs(1)),
+        tables.phase1(),
+        tables.phase2(),
+    )
+    .ok_or_else(|| Failure::NoSolution("No solution found".to_string()))?;
+    let elapsed = start.elapsed();
+    progress!("Elapsed: {:?}", elapsed);
+
+    let solution = Algorithm::from(path);
+    match settings.format {
+        Format::Plain => println!("Solution Found: {solution}"),
+        Format::Json => println!(
+            "{}",
+            Json::new()
+                .string("solution", &solution.to_string())
+                .number("length", solution.length(Metric::Htm))
+                .string("metric", "htm")
+                .number("seconds", elapsed.as_secs_f64())
+                .finish()
+        ),
+    }
+    Ok(())
+}
+
+fn solve_thistlethwaite(settings: &Settings, scramble: &Cube) -> Result<(), Failure> {
+    let dir = settings.options.dir.as_path();
+    progress!("Loading Thistlethwaite pruning tables...");
+    let start = Instant::now();
+    let tables = thistlethwaite::Tables {
+        phase1: load_pruning_table(dir, "thistlethwaite_phase1_pruning_table", Metric::Htm),
+        phase2: load_pruning_table(dir, "thistlethwaite_phase2_pruning_table", Metric::Htm),
+        phase3: load_pruning_table(dir, "thistlethwaite_phase3_pruning_table", Metric::Htm),
+        phase4: load_pruning_table(dir, "thistlethwaite_phase4_pruning_table", Metric::Htm),
+    };
+    progress!(
+        "Loaded Thistlethwaite pruning tables in {:?}",
+        start.elapsed()
+    );
+
+    let start = Instant::now();
+    let phases = thistlethwaite::thistlethwaite(scramble, &tables)
+        .map_err(|e| Failure::Invalid(format!("Invalid cube: {e}")))?;
+    let elapsed = start.elapsed();
+    progress!("Elapsed: {:?}", elapsed);
+
+    let json_phases: Vec<String> = phases
+        .iter()
+        .map(|phase| {
+            Json::new()
+                .string("reached", &phase.reached.to_string())
+                .string("goal", phase.reached.goal())
+                .string("moves", &Algorithm::from(phase.moves.clone()).to_string())
+                .finish()
+        })
+        .collect();
+    if settings.format == Format::Plain {
+        for phase in &phases {
+            println!(
+                "{} ({}), {} moves: {}",
+                phase.reached,
+                phase.reached.goal(),
+                phase.moves.len(),
+                Algorithm::from(phase.moves.clone())
+            );
+        }
+    }
+
+    let solution: Algorithm = phases.into_iter().flat_map(|p| p.moves).collect();
+    match settings.format {
+        Format::Plain => println!(
+            "Solution Found ({} moves): {}",
+            solution.moves().len(),
+            solution
         ),
-    ) {
-        let elapsed = start.elapsed();
-        eprintln!("Elapsed: {:?}", elapsed);
+        Format::Json => println!(
+            "{}",
+            Json::new()
+                .string("solution", &solution.to_string())
+                .number("length", solution.moves().len())
+                .raw("phases", &format!("[{}]", json_phases.join(",")))
+                .number("seconds", elapsed.as_secs_f64())
+                .finish()
+        ),
+    }
+    Ok(())
+}
+
+/// uniformly random states, each printed as the inverse of a two phase solution of it
+fn scramble(args: &Args, settings: &Settings) -> Result<(), Failure> {
+    let count = args.parsed::<usize>("--count")?.unwrap_or(1);
+    let (mut rng, seed) = match args.parsed::<u64>("--seed")? {
+        Some(seed) => (Rng::new(seed), seed),
+        None => Rng::from_clock(),
+    };
+    // the seed reproduces the same scrambles
+    progress!("Seed: {seed}");
+
+    let tables = TwoPhaseTables::load(&settings.options.dir);
+    let target = settings.max_depth.unwrap_or(20);
+    let time_limit = settings.time_limit.unwrap_or(Duration::from_secs(1));
+
+    let mut scrambles = Vec::new();
+    for _ in 0..count {
+        let cube = Cube::random(&mut rng);
+        // the states are valid, so there's only no scramble when the time limit runs out first
+        let Some(scramble) =
+            random::scramble_for(&cube, target, time_limit, tables.phase1(), tables.phase2())
+        else {
+            return Err(Failure::NoSolution(format!(
+                "No scramble found for {} in {time_limit:.2?}",
+                cube.to_facelet_str()
+            )));
+        };
+
+        match settings.format {
+            Format::Plain if settings.verbosity == Verbosity::Verbose => {
+                println!("{scramble}\t{}", cube.to_facelet_str())
+            }
+            Format::Plain => println!("{scramble}"),
+            Format::Json => scrambles.push(
+                Json::new()
+                    .string("scramble", &scramble.to_string())
+                    .number("length", scramble.length(Metric::Htm))
+                    .string("facelets", &cube.to_facelet_str())
+                    .finish(),
+            ),
+        }
+    }
+
+    if settings.format == Format::Json {
         println!(
-            "Solution Found: {}",
-            path.into_iter()
-                .map(|m| m.to_str())
-                .collect::<Vec<_>>()
-                .join(" ")
+            "{}",
+            Json::new()
+                .number("seed", seed)
+                .raw("scrambles", &format!("[{}]", scrambles.join(",")))
+                .finish()
+        );
+    }
+    Ok(())
+}
+
+fn show(args: &Args, settings: &Settings) -> Result<(), Failure> {
+    let cube = read_cube(args)?;
+    match settings.format {
+        Format::Plain => {
+            cube.print_net();
+            if settings.verbosity == Verbosity::Verbose {
+                println!("{}", cube.to_facelet_str());
+            }
+        }
+        Format::Json => println!(
+            "{}",
+            Json::new()
+                .string("facelets", &cube.to_facelet_str())
+                .finish()
+        ),
+    }
+    Ok(())
+}
+
+fn bench(args: &Args, settings: &Settings) -> Result<(), Failure> {
+    let scrambles: Vec<String> = if args.positional.is_empty() {
+        BENCH_SCRAMBLES.map(str::to_string).to_vec()
+    } else {
+        args.positional.clone()
+    };
+    let scrambles = scrambles
+        .into_iter()
+        .map(|scramble| {
+            let cube = read_scramble(&scramble)?;
+            cube.validate()
+                .map_err(|e| Failure::Invalid(format!("Invalid cube {scramble}: {e}")))?;
+            Ok((scramble, cube))
+        })
+        .collect::<Result<Vec<_>, Failure>>()?;
+    if args.flag("--two-phase") {
+        return bench_two_phase(settings, &scrambles);
+    }
+
+    let solver = build_solver(settings)?;
+    let metric = settings.metric;
+    let mut total_nodes = 0;
+    let mut total_time = Duration::ZERO;
+    let mut results = Vec::new();
+    for (scramble, cube) in &scrambles {
+        let result = solve_optimal(&solver, cube)?;
+        total_nodes += result.nodes;
+        total_time += result.elapsed;
+        if settings.format == Format::Plain {
+            println!(
+                "{scramble}: {} moves, {} nodes in {:.2?}, {}",
+                result.depth, result.nodes, result.elapsed, result.solution
+            );
+        }
+        let json = Json::new().string("scramble", scramble);
+        results.push(result_to_json(&result, json, metric).finish());
+    }
+
+    let rate = total_nodes as f64 / total_time.as_secs_f64();
+    match settings.format {
+        Format::Plain => println!(
+            "Searched {total_nodes} nodes in {total_time:.2?} at {:.2}M nodes/s",
+            rate / 1_000_000.0
+        ),
+        Format::Json => println!(
+            "{}",
+            Json::new()
+                .raw("results", &format!("[{}]", results.join(",")))
+                .number("nodes", total_nodes)
+                .number("seconds", total_time.as_secs_f64())
+                .number("nodes_per_second", rate.round())
+                .finish()
+        ),
+    }
+    Ok(())
+}
+
+/// searches each scramble with Kociemba's algorithm for the time limit, once turning the
+/// coordinates the tables look up through move tables and once turning whole cubes. Both search
+/// the same nodes in the same order, so the rates show how much the move tables save
+fn bench_two_phase(settings: &Settings, scrambles: &[(String, Cube)]) -> Result<(), Failure> {
+    let tables = TwoPhaseTables::load(&settings.options.dir);
+    // the move tables are built the first time they're used, which shouldn't be timed
+    let _ = (Phase1Cube::solved(), Phase2Cube::solved());
+    let time_limit = settings.time_limit.unwrap_or(Duration::from_secs(1));
+    // no solution is that short, so each search runs until the time limit
+    let target = 0;
+
+    let rate = |nodes: u64, elapsed: Duration| nodes as f64 / elapsed.as_secs_f64();
+    let mut totals = [(0, Duration::ZERO); 2];
+    let mut results = Vec::new();
+    for (scramble, cube) in scrambles {
+        let start = Instant::now();
+        let (_, coord_nodes) =
+            two_phase_counted(cube, target, time_limit, tables.phase1(), tables.phase2());
+        let coord_time = start.elapsed();
+
+        let start = Instant::now();
+        let (_, cube_nodes) = two_phase_counted(
+            cube,
+            target,
+            time_limit,
+            tables.cube_phase1(),
+            tables.cube_phase2(),
         );
+        let cube_time = start.elapsed();
+
+        let (coord_rate, cube_rate) = (rate(coord_nodes, coord_time), rate(cube_nodes, cube_time));
+        if settings.format == Format::Plain {
+            println!(
+                "{scramble}: {:.2}M nodes/s on coordinates, {:.2}M nodes/s on cubes",
+                coord_rate / 1_000_000.0,
+                cube_rate / 1_000_000.0
+            );
+        }
+        results.push(
+            Json::new()
+                .string("scramble", scramble)
+                .number("coordinate_nodes_per_second", coord_rate.round())
+                .number("cube_nodes_per_second", cube_rate.round())
+                .finish(),
+        );
+        totals[0].0 += coord_nodes;
+        totals[0].1 += coord_time;
+        totals[1].0 += cube_nodes;
+        totals[1].1 += cube_time;
+    }
+
+    let [coord_rate, cube_rate] = totals.map(|(nodes, elapsed)| rate(nodes, elapsed));
+    match settings.format {
+        Format::Plain => println!(
+            "Searched {:.2}M nodes/s on coordinates and {:.2}M nodes/s on cubes, {:.1}x as fast",
+            coord_rate / 1_000_000.0,
+            cube_rate / 1_000_000.0,
+            coord_rate / cube_rate
+        ),
+        Format::Json => println!(
+            "{}",
+            Json::new()
+                .raw("results", &format!("[{}]", results.join(",")))
+                .number("coordinate_nodes_per_second", coord_rate.round())
+                .number("cube_nodes_per_second", cube_rate.round())
+                .finish()
+        ),
     }
+    Ok(())
+}
+
+/// the scrambles of a `batch` file and where their results go
+struct Batch {
+    inputs: Vec<(String, Cube)>,
+    jobs: usize,
+    output: Option<OutputFile>,
+}
+
+/// a file for the result of each scramble
+enum OutputFile {
+    Csv(PathBuf),
+    Json(PathBuf),
 }
 
-pub fn load_pruning_table<S, T>(path: impl AsRef<std::path::Path>) -> Box<PruningTable<S, T>>
-where
-    S: Puzzle + Clone + std::ops::Mul<crate::mv::Move, Output = S>,
-    T: Coordinate<S>,
-    [u8; T::MAX]: Sized,
-{
-    match std::fs::read(path.as_ref()) {
-        Ok(data) => PruningTable::new(data.try_into().unwrap()),
-        Err(e) => {
-            eprintln!("Error reading pruning table: {}", e);
-            let table = PruningTable::generate();
-            std::fs::write(path, table.as_ref()).expect("Failed to write pruning table");
-            table
+fn batch(args: &Args, settings: &Settings) -> Result<(), Failure> {
+    let [path] = args.positional.as_slice() else {
+        return Err(Failure::Usage(
+            "Expected a single file of scrambles".to_string(),
+        ));
+    };
+    let file = match path.as_str() {
+        "-" => std::io::read_to_string(std::io::stdin()),
+        path => std::fs::read_to_string(path),
+    }
+    .map_err(|e| Failure::Usage(format!("Can't read {path}: {e}")))?;
+
+    // every line is checked before the tables are loaded
+    let mut inputs = Vec::new();
+    for (line, input) in file.lines().enumerate() {
+        let input = input.split('#').next().unwrap().trim();
+        if input.is_empty() {
+            continue;
+        }
+        let cube = read_scramble(input)
+            .and_then(|cube| {
+                cube.validate()
+                    .map_err(|e| Failure::Invalid(format!("Invalid cube: {e}")))?;
+                Ok(cube)
+            })
+            .map_err(|e| match e {
+                Failure::Invalid(e) => Failure::Invalid(format!("{path}:{}: {e}", line + 1)),
+                e => e,
+            })?;
+        inputs.push((input.to_string(), cube));
+    }
+
+    let output = match args.value("--output") {
+        Some(output) => {
+            let path = PathBuf::from(output);
+            match output.rsplit_once('.').map(|(_, extension)| extension) {
+                Some("csv") => Some(OutputFile::Csv(path)),
+                Some("json") => Some(OutputFile::Json(path)),
+                _ => {
+                    return Err(Failure::Usage(format!(
+                        "Can't tell the format of {output}, expected a .csv or .json file"
+                    )));
+                }
+            }
         }
+        None => None,
+    };
+    let batch = Batch {
+        inputs,
+        jobs: args.parsed::<usize>("--jobs")?.unwrap_or(1).max(1),
+        output,
+    };
+    let solver = build_solver(settings)?;
+    run_batch(settings, &batch, &solver)
+}
+
+fn run_batch(settings: &Settings, batch: &Batch, solver: &Solver) -> Result<(), Failure> {
+    let metric = settings.metric;
+    let cubes: Vec<Cube> = batch.inputs.iter().map(|(_, cube)| cube.clone()).collect();
+
+    let start = Instant::now();
+    let mut done = 0;
+    let results = solver.solve_batch(&cubes, batch.jobs, None, |i, result| {
+        done += 1;
+        let input = &batch.inputs[i].0;
+        match &result.solution {
+            Some(solution) => progress!(
+                "[{done}/{}] {input}: {} moves in {:.2?}",
+                cubes.len(),
+                solution.length(metric),
+                result.elapsed
+            ),
+            None => progress!(
+                "[{done}/{}] {input}: no solution in {:.2?}",
+                cubes.len(),
+                result.elapsed
+            ),
+        }
+    });
+    let summary = BatchSummary::new(&results, metric, start.elapsed());
+
+    let length = |result: &BatchResult| result.solution.as_ref().map(|s| s.length(metric));
+    let rows: Vec<Json> = batch
+        .inputs
+        .iter()
+        .zip(&results)
+        .map(|((input, _), result)| {
+            let json = Json::new().string("scramble", input);
+            let json = match &result.solution {
+                Some(solution) => json.string("solution", &solution.to_string()),
+                None => json.raw("solution", "null"),
+            };
+            match length(result) {
+                Some(length) => json.number("length", length),
+                None => json.raw("length", "null"),
+            }
+            .number("nodes", result.nodes)
+            .number("seconds", result.elapsed.as_secs_f64())
+        })
+        .collect();
+
+    let summary_json = summary_to_json(&summary, metric);
+    match &batch.output {
+        Some(OutputFile::Csv(path)) => {
+            let mut csv = "scramble,solution,length,nodes,seconds\n".to_string();
+            for ((input, _), result) in batch.inputs.iter().zip(&results) {
+                let solution = result.solution.as_ref().map(ToString::to_string);
+                csv += &format!(
+                    "{},{},{},{},{}\n",
+                    csv_field(input),
+                    csv_field(solution.as_deref().unwrap_or("")),
+                    length(result).map_or(String::new(), |l| l.to_string()),
+                    result.nodes,
+                    result.elapsed.as_secs_f64()
+                );
+            }
+            write_output(path, &csv)?;
+        }
+        Some(OutputFile::Json(path)) => {
+            let rows: Vec<String> = rows.into_iter().map(Json::finish).collect();
+            write_output(path, &format!("[{}]\n", rows.join(",")))?;
+        }
+        None if settings.format == Format::Plain => {
+            for ((input, _), result) in batch.inputs.iter().zip(&results) {
+                match &result.solution {
+                    Some(solution) => println!(
+                        "{input}: {} moves, {} nodes in {:.2?}, {solution}",
+                        solution.length(metric),
+                        result.nodes,
+                        result.elapsed
+                    ),
+                    None => println!(
+                        "{input}: no solution, {} nodes in {:.2?}",
+                        result.nodes, result.elapsed
+                    ),
+                }
+            }
+            println!();
+        }
+        None => {
+            let rows: Vec<String> = rows.into_iter().map(Json::finish).collect();
+            println!(
+                "{}",
+                Json::new()
+                    .raw("results", &format!("[{}]", rows.join(",")))
+                    .raw("summary", &summary_json)
+                    .finish()
+            );
+        }
+    }
+
+    match (&batch.output, settings.format) {
+        (_, Format::Plain) => print_summary(&summary, metric),
+        (Some(_), Format::Json) => println!("{summary_json}"),
+        (None, Format::Json) => {}
+    }
+
+    match summary.unsolved {
+        0 => Ok(()),
+        unsolved => Err(Failure::NoSolution(format!(
+            "{unsolved} of {} scrambles weren't solved",
+            results.len()
+        ))),
+    }
+}
+
+fn write_output(path: &Path, contents: &str) -> Result<(), Failure> {
+    std::fs::write(path, contents)
+        .map_err(|e| Failure::Usage(format!("Can't write {}: {e}", path.display())))
+}
+
+fn print_summary(summary: &BatchSummary, metric: Metric) {
+    println!(
+        "Solved {} of {} scrambles in {:.2?}",
+        summary.solved,
+        summary.solved + summary.unsolved,
+        summary.elapsed
+    );
+    for (length, count) in &summary.lengths {
+        println!("  {length} {metric:?}: {count}");
+    }
+    println!(
+        "Mean time {:.2?}, median {:.2?}",
+        summary.mean_time, summary.median_time
+    );
+    println!(
+        "Searched {} nodes at {:.2}M nodes/s",
+        summary.total_nodes,
+        summary.nodes_per_second / 1_000_000.0
+    );
+}
+
+fn summary_to_json(summary: &BatchSummary, metric: Metric) -> String {
+    let lengths = summary
+        .lengths
+        .iter()
+        .fold(Json::new(), |json, (length, count)| {
+            json.number(&length.to_string(), count)
+        });
+    Json::new()
+        .number("solved", summary.solved)
+        .number("unsolved", summary.unsolved)
+        .string("metric", &format!("{metric:?}").to_lowercase())
+        .raw("lengths", &lengths.finish())
+        .number("mean_seconds", summary.mean_time.as_secs_f64())
+        .number("median_seconds", summary.median_time.as_secs_f64())
+        .number("nodes", summary.total_nodes)
+        .number("seconds", summary.elapsed.as_secs_f64())
+        .number("nodes_per_second", summary.nodes_per_second.round())
+        .finish()
+}
+
+/// quoted when it has a comma, quote or new line
+fn csv_field(field: &str) -> String {
+    match field.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+/// `tables generate`, `tables verify` or `tables info`, for the listed tables or the heuristic set
+fn tables(args: &Args, settings: &Settings) -> Result<(), Failure> {
+    let Some((action, names)) = args.positional.split_first() else {
+        return Err(Failure::Usage(
+            "Expected tables generate, verify or info".to_string(),
+        ));
+    };
+    let names = if names.is_empty() {
+        settings.table_names()
+    } else {
+        names.to_vec()
+    };
+
+    // each table once, however many ways the heuristic set looks it up
+    let mut tables = Vec::new();
+    for name in &names {
+        let (name, _) = parse_table(name).map_err(Failure::Usage)?;
+        if !tables.contains(&name) {
+            tables.push(name);
+        }
+    }
+    let metric = settings.metric;
+    let options = &settings.options;
+
+    match action.as_str() {
+        "generate" => {
+            for name in tables {
+                load_heuristics(&[name], metric, options).map_err(Failure::Tables)?;
+            }
+            Ok(())
+        }
+        "verify" => {
+            let mut failed = 0;
+            let mut results = Vec::new();
+            for name in tables {
+                let (path, kind) = table_file(name, metric, options).map_err(Failure::Usage)?;
+                let error = read_table(&path, &kind).err().map(|e| e.to_string());
+                match (settings.format, &error) {
+                    (Format::Plain, None) => println!("{name}: {} ok", path.display()),
+                    (Format::Plain, Some(e)) => println!("{name}: {}: {e}", path.display()),
+                    (Format::Json, _) => results.push(
+                        Json::new()
+                            .string("table", name)
+                            .string("path", &path.to_string_lossy())
+                            .boolean("ok", error.is_none())
+                            .optional_string("error", error.as_deref())
+                            .finish(),
+                    ),
+                }
+                failed += error.is_some() as usize;
+            }
+
+            if settings.format == Format::Json {
+                println!("[{}]", results.join(","));
+            }
+            match failed {
+                0 => Ok(()),
+                failed => Err(Failure::Tables(format!("{failed} tables failed to verify"))),
+            }
+        }
+        "info" => {
+            let mut results = Vec::new();
+            for name in tables {
+                let (path, kind) = table_file(name, metric, options).map_err(Failure::Usage)?;
+                let header = read_header(&path);
+                let bytes = std::fs::metadata(&path).map_or(0, |m| m.len());
+
+                match settings.format {
+                    Format::Plain => {
+                        println!("{name}: {}", path.display());
+                        match &header {
+                            Ok(header) => {
+                                println!("  coordinate {}", header.coordinate);
+                                println!("  encoding {}", header.encoding);
+                                println!("  metric {}", header.metric);
+                                println!("  moves {}", header.moves);
+                                println!("  {} coordinates, {bytes} bytes", header.size);
+                                println!("  checksum {:016x}", header.checksum);
+                            }
+                            Err(e) => println!("  {e}"),
+                        }
+                        if settings.verbosity == Verbosity::Verbose {
+                            println!("  expected coordinate {}", kind.coordinate);
+                        }
+                    }
+                    Format::Json => {
+                        let json = Json::new()
+                            .string("table", name)
+                            .string("path", &path.to_string_lossy());
+                        results.push(match &header {
+                            Ok(header) => json
+                                .string("coordinate", &header.coordinate)
+                                .string("encoding", &header.encoding)
+                                .string("metric", &header.metric)
+                                .string("moves", &header.moves)
+                                .number("size", header.size)
+                                .number("bytes", bytes)
+                                .string("checksum", &format!("{:016x}", header.checksum))
+                                .finish(),
+                            Err(e) => json.string("error", &e.to_string()).finish(),
+                        });
+                    }
+                }
+            }
+
+            if settings.format == Format::Json {
+                println!("[{}]", results.join(","));
+            }
+            Ok(())
+        }
+        action => Err(Failure::Usage(format!(
+            "Unknown tables command {action}, expected generate, verify or info"
+        ))),
+    }
+}
+
+/// a JSON object written a field at a time, enough for the flat results printed here
+struct Json(String);
+
+impl Json {
+    fn new() -> Self {
+        Json(String::new())
+    }
+
+    fn raw(mut self, key: &str, value: &str) -> Self {
+        self.0.push_str(if self.0.is_empty() { "{" } else { "," });
+        self.0.push_str(&json_string(key));
+        self.0.push(':');
+        self.0.push_str(value);
+        self
+    }
+
+    fn string(self, key: &str, value: &str) -> Self {
+        self.raw(key, &json_string(value))
+    }
+
+    fn optional_string(self, key: &str, value: Option<&str>) -> Self {
+        match value {
+            Some(value) => self.string(key, value),
+            None => self.raw(key, "null"),
+        }
+    }
+
+    fn number(self, key: &str, value: impl std::fmt::Display) -> Self {
+        self.raw(key, &value.to_string())
+    }
+
+    fn boolean(self, key: &str, value: bool) -> Self {
+        self.raw(key, &value.to_string())
+    }
+
+    fn finish(self) -> String {
+        match self.0.is_empty() {
+            true => "{}".to_string(),
+            false => self.0 + "}",
+        }
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Args, Failure> {
+        Args::parse(&args.split(' ').map(str::to_string).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn arguments() {
+        let args = parse("solve --facelets UUU --max-depth=12 -q --all=2")
+            .ok()
+            .unwrap();
+        assert_eq!(args.command, "solve");
+        assert!(args.positional.is_empty());
+        assert_eq!(args.value("--facelets"), Some("UUU"));
+        assert_eq!(args.parsed::<u8>("--max-depth").ok().unwrap(), Some(12));
+        assert_eq!(args.value("--all"), Some("2"));
+        assert!(args.flag("-q") && !args.flag("--all"));
+
+        // a scramble without a command is solved
+        let args = parse("R U2 --metric qtm").ok().unwrap();
+        assert_eq!(args.command, "solve");
+        assert_eq!(args.positional, ["R", "U2"]);
+        assert_eq!(
+            read_cube(&args).ok().unwrap(),
+            "R U2".parse::<Algorithm>().unwrap().to_cube()
+        );
+        let args = parse("show R U --facelets UUU").ok().unwrap();
+        assert!(matches!(read_cube(&args), Err(Failure::Usage(_))));
+        assert_eq!(parse("tables info --help").ok().unwrap().command, "help");
+
+        assert!(matches!(parse("solve --frob"), Err(Failure::Usage(_))));
+        assert!(matches!(parse("solve R --metric"), Err(Failure::Usage(_))));
+        assert!(matches!(parse("--mmap"), Err(Failure::Usage(_))));
+        let args = parse("bench --threads=x").ok().unwrap();
+        assert!(matches!(
+            args.parsed::<usize>("--threads"),
+            Err(Failure::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn csv_fields() {
+        assert_eq!(csv_field("R U' F2"), "R U' F2");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn json() {
+        let json = Json::new()
+            .string("solution", "R U'")
+            .number("length", 2)
+            .optional_string("error", None)
+            .raw("moves", "[\"R\"]")
+            .finish();
+        assert_eq!(
+            json,
+            r#"{"solution":"R U'","length":2,"error":null,"moves":["R"]}"#
+        );
+        assert_eq!(Json::new().finish(), "{}");
+        assert_eq!(json_string("a\"b\\\n\u{1}"), r#""a\"b\\\n\u0001""#);
     }
 }
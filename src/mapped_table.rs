@@ -0,0 +1,123 @@
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::pruning_table::{Coordinate, PruningTable};
+use crate::table_file::{TableFileError, TableKind, check_header};
+
+/// a byte per entry pruning table used straight from its file through a read only memory map.
+/// Pages are only read once a lookup needs them and are shared by every process mapping the same
+/// file, so there's nothing to wait for at startup. The checksum isn't checked since that would
+/// read the whole table
+pub struct MappedTable<S, T> {
+    map: Mmap,
+    /// where the data starts, after the header
+    offset: usize,
+    coordinate: PhantomData<fn() -> (S, T)>,
+}
+
+impl<S, T: Coordinate<S>> MappedTable<S, T>
+where
+    [u8; T::MAX]: Sized,
+{
+    pub fn open(path: impl AsRef<Path>, kind: &TableKind) -> Result<Self, TableFileError> {
+        let file = std::fs::File::open(path)?;
+        // table files are replaced rather than written over, so the mapped file never changes
+        let map = unsafe { Mmap::map(&file)? };
+        // lookups jump all over the table, reading ahead would only load pages that aren't used
+        #[cfg(unix)]
+        map.advise(memmap2::Advice::Random)?;
+
+        let (offset, _) = check_header(&map, kind)?;
+        if map.len() - offset != T::MAX {
+            return Err(TableFileError::Length {
+                expected: T::MAX as u64,
+                found: (map.len() - offset) as u64,
+            });
+        }
+
+        Ok(MappedTable {
+            map,
+            offset,
+            coordinate: PhantomData,
+        })
+    }
+}
+
+impl<S, T: Coordinate<S>> Deref for MappedTable<S, T>
+where
+    [u8; T::MAX]: Sized,
+{
+    type Target = PruningTable<S, T>;
+
+    fn deref(&self) -> &PruningTable<S, T> {
+        let data = &self.map[self.offset..];
+        // PruningTable is a transparent [u8; T::MAX] and `open` checked there are T::MAX bytes
+        unsafe { &*(data.as_ptr() as *const PruningTable<S, T>) }
+    }
+}
+
+/// a byte per entry table either read into memory or mapped, used through `&PruningTable` either
+/// way
+pub enum LoadedTable<S, T: Coordinate<S>>
+where
+    [u8; T::MAX]: Sized,
+{
+    Read(Box<PruningTable<S, T>>),
+    Mapped(MappedTable<S, T>),
+}
+
+impl<S, T: Coordinate<S>> Deref for LoadedTable<S, T>
+where
+    [u8; T::MAX]: Sized,
+{
+    type Target = PruningTable<S, T>;
+
+    fn deref(&self) -> &PruningTable<S, T> {
+        match self {
+            LoadedTable::Read(table) => table,
+            LoadedTable::Mapped(table) => table,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::{Cube, SOLVED};
+    use crate::heuristics::Heuristic;
+    use crate::mv::Metric;
+    use crate::mv::Move::{self, *};
+    use crate::pruning_table::CornerOrientation;
+    use crate::table_file::write_table;
+
+    #[test]
+    fn matches_generated_table() {
+        let path = std::env::temp_dir()
+            .join(format!("cube_solver_mapped_{}.bin", std::process::id()))
+            .to_string_lossy()
+            .into_owned();
+        let kind = TableKind::new::<Cube, CornerOrientation>("byte", Metric::Htm, Move::ALL);
+        let table = PruningTable::<Cube, CornerOrientation>::generate();
+        write_table(&path, &kind, (*table).as_ref()).unwrap();
+
+        let mapped = MappedTable::<Cube, CornerOrientation>::open(&path, &kind).unwrap();
+        assert_eq!((*mapped).as_ref(), (*table).as_ref());
+
+        let mut cube = SOLVED;
+        for mv in [R, U, F2, L3, D] {
+            cube = cube * mv;
+            assert_eq!(
+                (&*mapped).lower_bound(&cube),
+                table.as_ref().lower_bound(&cube)
+            );
+        }
+
+        let qtm = TableKind::new::<Cube, CornerOrientation>("byte", Metric::Qtm, Move::ALL);
+        assert!(MappedTable::<Cube, CornerOrientation>::open(&path, &qtm).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,440 @@
+use std::any::{TypeId, type_name};
+use std::marker::PhantomData;
+use std::sync::OnceLock;
+
+use crate::cube::Cube;
+use crate::heuristics::Heuristic;
+use crate::mv::Move;
+use crate::pruning_table::{
+    Coordinate, CornerOrientation, CornerPermutation, EO, PruningTable, SlicePermutation,
+    UDEdgePermutation, UDSlice,
+};
+use crate::puzzle::Puzzle;
+
+/// where every move takes every coordinate, so a search can turn coordinates without building a
+/// full puzzle for each one
+pub struct MoveTable<S, T> {
+    /// the column of each move, indexed by the move's position in `Move::EVERY`, `u8::MAX` for
+    /// moves the table wasn't generated with
+    columns: [u8; Move::EVERY.len()],
+    width: usize,
+    table: Box<[u32]>,
+    solved: u32,
+    coordinate: PhantomData<fn() -> (S, T)>,
+}
+
+impl<S, T> MoveTable<S, T>
+where
+    S: Puzzle + Clone + std::ops::Mul<Move, Output = S>,
+    T: Coordinate<S>,
+{
+    /// `moves` have to keep the puzzle in states the coordinate can describe, like `T::MOVES`
+    pub fn generate(moves: &[Move]) -> Self {
+        let mut columns = [u8::MAX; Move::EVERY.len()];
+        for (column, &mv) in moves.iter().enumerate() {
+            columns[mv as usize] = column as u8;
+        }
+
+        let mut table = vec![0; T::MAX * moves.len()];
+        for (coord, row) in table.chunks_exact_mut(moves.len()).enumerate() {
+            let puzzle = T::from_coord(coord);
+            for (entry, &mv) in row.iter_mut().zip(moves) {
+                *entry = T::to_coord(&(puzzle.clone() * mv).reoriented()) as u32;
+            }
+        }
+
+        MoveTable {
+            columns,
+            width: moves.len(),
+            table: table.into(),
+            solved: T::to_coord(&S::solved()) as u32,
+            coordinate: PhantomData,
+        }
+    }
+}
+
+impl<S, T> MoveTable<S, T> {
+    /// the coordinate after turning `mv` from `coord`, panics if the table doesn't have `mv`
+    pub fn apply(&self, coord: u32, mv: Move) -> u32 {
+        let column = self.columns[mv as usize];
+        assert!(column != u8::MAX, "{mv:?} isn't in the move table");
+        self.table[coord as usize * self.width + column as usize]
+    }
+
+    /// `apply`, or `None` if the table doesn't have `mv`
+    pub fn get(&self, coord: usize, mv: Move) -> Option<usize> {
+        let column = self.columns[mv as usize];
+        (column != u8::MAX).then(|| self.table[coord * self.width + column as usize] as usize)
+    }
+
+    pub fn solved(&self) -> u32 {
+        self.solved
+    }
+}
+
+/// a coordinate of the cube with a move table that's built once, the first time it's used
+pub trait MoveCoordinate: Coordinate<Cube> + Sized + 'static {
+    fn move_table() -> &'static MoveTable<Cube, Self>;
+}
+
+macro_rules! move_coordinate {
+    ($($coordinate:ty),*) => {$(
+        impl MoveCoordinate for $coordinate {
+            fn move_table() -> &'static MoveTable<Cube, Self> {
+                static TABLE: OnceLock<MoveTable<Cube, $coordinate>> = OnceLock::new();
+                TABLE.get_or_init(|| MoveTable::generate(Self::MOVES))
+            }
+        }
+    )*};
+}
+
+move_coordinate!(
+    CornerOrientation,
+    CornerPermutation,
+    EO,
+    UDSlice,
+    SlicePermutation,
+    UDEdgePermutation
+);
+
+/// several coordinates turned together, a tuple of `MoveCoordinate`s
+pub trait CoordinateTuple<const N: usize> {
+    fn to_coords(cube: &Cube) -> [u32; N];
+    fn solved() -> [u32; N];
+    fn apply(coords: &[u32; N], mv: Move) -> [u32; N];
+
+    /// which of the coordinates is `T`, panics if none of them are. The types are known when
+    /// this is compiled, so it's folded into a constant
+    fn position<T: 'static>() -> usize;
+}
+
+fn position<T: 'static>(types: &[TypeId]) -> usize {
+    types
+        .iter()
+        .position(|&id| id == TypeId::of::<T>())
+        .unwrap_or_else(|| panic!("{} isn't one of the coordinates", type_name::<T>()))
+}
+
+impl<T0: MoveCoordinate, T1: MoveCoordinate> CoordinateTuple<2> for (T0, T1) {
+    fn to_coords(cube: &Cube) -> [u32; 2] {
+        [T0::to_coord(cube) as u32, T1::to_coord(cube) as u32]
+    }
+
+    fn solved() -> [u32; 2] {
+        [T0::move_table().solved(), T1::move_table().solved()]
+    }
+
+    fn apply([c0, c1]: &[u32; 2], mv: Move) -> [u32; 2] {
+        [
+            T0::move_table().apply(*c0, mv),
+            T1::move_table().apply(*c1, mv),
+        ]
+    }
+
+    fn position<T: 'static>() -> usize {
+        position::<T>(&[TypeId::of::<T0>(), TypeId::of::<T1>()])
+    }
+}
+
+#[rustfmt::skip]
+impl<T0, T1, T2> CoordinateTuple<3> for (T0, T1, T2)
+where
+    T0: MoveCoordinate,
+    T1: MoveCoordinate,
+    T2: MoveCoordinate,
+{
+    fn to_coords(cube: &Cube) -> [u32; 3] {
+        [
+            T0::to_coord(cube) as u32,
+            T1::to_coord(cube) as u32,
+            T2::to_coord(cube) as u32,
+        ]
+    }
+
+    fn solved() -> [u32; 3] {
+        [
+            T0::move_table().solved(),
+            T1::move_table().solved(),
+            T2::move_table().solved(),
+        ]
+    }
+
+    fn apply([c0, c1, c2]: &[u32; 3], mv: Move) -> [u32; 3] {
+        [
+            T0::move_table().apply(*c0, mv),
+            T1::move_table().apply(*c1, mv),
+            T2::move_table().apply(*c2, mv),
+        ]
+    }
+
+    fn position<T: 'static>() -> usize {
+        position::<T>(&[TypeId::of::<T0>(), TypeId::of::<T1>(), TypeId::of::<T2>()])
+    }
+}
+
+/// the part of a cube described by the coordinates `C`, a puzzle that's turned by looking up
+/// move tables. Solved once all of its coordinates are, and it can only be turned with the moves
+/// every coordinate's table has
+pub struct CoordCube<C, const N: usize> {
+    pub coords: [u32; N],
+    coordinates: PhantomData<fn() -> C>,
+}
+
+impl<C: CoordinateTuple<N>, const N: usize> CoordCube<C, N> {
+    pub fn new(cube: &Cube) -> Self {
+        CoordCube {
+            coords: C::to_coords(cube),
+            coordinates: PhantomData,
+        }
+    }
+}
+
+// derives would require `C: Clone`
+impl<C, const N: usize> Clone for CoordCube<C, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C, const N: usize> Copy for CoordCube<C, N> {}
+
+impl<C, const N: usize> std::fmt::Debug for CoordCube<C, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("CoordCube").field(&self.coords).finish()
+    }
+}
+
+impl<C: CoordinateTuple<N>, const N: usize> Puzzle for CoordCube<C, N> {
+    fn solved() -> Self {
+        CoordCube {
+            coords: C::solved(),
+            coordinates: PhantomData,
+        }
+    }
+
+    fn is_solved(&self) -> bool {
+        self.coords == C::solved()
+    }
+}
+
+impl<C: CoordinateTuple<N>, const N: usize> std::ops::Mul<Move> for &CoordCube<C, N> {
+    type Output = CoordCube<C, N>;
+
+    fn mul(self, mv: Move) -> CoordCube<C, N> {
+        CoordCube {
+            coords: C::apply(&self.coords, mv),
+            coordinates: PhantomData,
+        }
+    }
+}
+
+impl<C: CoordinateTuple<N>, const N: usize> std::ops::Mul<Move> for CoordCube<C, N> {
+    type Output = CoordCube<C, N>;
+
+    fn mul(self, mv: Move) -> CoordCube<C, N> {
+        &self * mv
+    }
+}
+
+/// a pruning table of one of the coordinates of a `CoordCube`, looked up without a full cube
+pub struct Single<'a, T: Coordinate<Cube>>(pub &'a PruningTable<Cube, T>)
+where
+    [u8; T::MAX]: Sized;
+
+/// a pruning table of a pair of the coordinates of a `CoordCube`. Pairs of coordinates are
+/// numbered `c0 * C1::MAX + c1`, so the index comes straight from the two
+pub struct Pair<'a, C0, C1>(pub &'a PruningTable<Cube, (C0, C1)>)
+where
+    (C0, C1): Coordinate<Cube>,
+    [u8; <(C0, C1)>::MAX]: Sized;
+
+// derives would require the coordinates to be Clone
+impl<T: Coordinate<Cube>> Clone for Single<'_, T>
+where
+    [u8; T::MAX]: Sized,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Coordinate<Cube>> Copy for Single<'_, T> where [u8; T::MAX]: Sized {}
+
+impl<C0, C1> Clone for Pair<'_, C0, C1>
+where
+    (C0, C1): Coordinate<Cube>,
+    [u8; <(C0, C1)>::MAX]: Sized,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C0, C1> Copy for Pair<'_, C0, C1>
+where
+    (C0, C1): Coordinate<Cube>,
+    [u8; <(C0, C1)>::MAX]: Sized,
+{
+}
+
+impl<C, T, const N: usize> Heuristic<CoordCube<C, N>> for Single<'_, T>
+where
+    C: CoordinateTuple<N>,
+    T: Coordinate<Cube> + 'static,
+    [u8; T::MAX]: Sized,
+{
+    type Memo = u8;
+
+    fn lower_bound(self, state: &CoordCube<C, N>) -> u8 {
+        self.0.as_ref()[state.coords[C::position::<T>()] as usize]
+    }
+
+    fn memo(self, state: &CoordCube<C, N>) -> u8 {
+        self.lower_bound(state)
+    }
+
+    fn next_memo(self, state: &CoordCube<C, N>, _parent: u8) -> u8 {
+        self.lower_bound(state)
+    }
+
+    fn memo_bound(self, memo: u8) -> u8 {
+        memo
+    }
+}
+
+impl<C, C0, C1, const N: usize> Heuristic<CoordCube<C, N>> for Pair<'_, C0, C1>
+where
+    C: CoordinateTuple<N>,
+    C0: 'static,
+    C1: Coordinate<Cube> + 'static,
+    (C0, C1): Coordinate<Cube>,
+    [u8; <(C0, C1)>::MAX]: Sized,
+{
+    type Memo = u8;
+
+    fn lower_bound(self, state: &CoordCube<C, N>) -> u8 {
+        let (c0, c1) = (
+            state.coords[C::position::<C0>()],
+            state.coords[C::position::<C1>()],
+        );
+        self.0.as_ref()[c0 as usize * C1::MAX + c1 as usize]
+    }
+
+    fn memo(self, state: &CoordCube<C, N>) -> u8 {
+        self.lower_bound(state)
+    }
+
+    fn next_memo(self, state: &CoordCube<C, N>, _parent: u8) -> u8 {
+        self.lower_bound(state)
+    }
+
+    fn memo_bound(self, memo: u8) -> u8 {
+        memo
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::Algorithm;
+    use crate::cube::ida;
+    use crate::heuristics::ZeroBound;
+    use crate::mv::Move::*;
+
+    fn scrambles() -> impl Iterator<Item = Cube> {
+        [
+            "R U2 F' L B D R",
+            "F2 D' L U B2 R' F",
+            "U R2 B L' D2 F R' U2 L",
+        ]
+        .into_iter()
+        .map(|s| s.parse::<Algorithm>().unwrap().to_cube())
+    }
+
+    fn assert_matches_cube<T: MoveCoordinate>(scramble: &Cube) {
+        let table = T::move_table();
+        for &mv in T::MOVES {
+            assert_eq!(
+                table.apply(T::to_coord(scramble) as u32, mv) as usize,
+                T::to_coord(&(scramble * mv)),
+                "{} {mv:?}",
+                std::any::type_name::<T>()
+            );
+        }
+    }
+
+    #[test]
+    fn move_tables() {
+        for scramble in scrambles() {
+            assert_matches_cube::<CornerOrientation>(&scramble);
+            assert_matches_cube::<CornerPermutation>(&scramble);
+            assert_matches_cube::<EO>(&scramble);
+            assert_matches_cube::<UDSlice>(&scramble);
+        }
+
+        let domino = [U, R2, D3, F2, L2, U2, B2]
+            .into_iter()
+            .map(Move::to_cube)
+            .product();
+        assert_matches_cube::<SlicePermutation>(&domino);
+        assert_matches_cube::<UDEdgePermutation>(&domino);
+
+        // pruning tables of tuples are generated by turning each coordinate
+        type CoSlice = (CornerOrientation, UDSlice);
+        for scramble in scrambles() {
+            for &mv in Move::ALL {
+                assert_eq!(
+                    CoSlice::turn(CoSlice::to_coord(&scramble), mv),
+                    Some(CoSlice::to_coord(&(&scramble * mv)))
+                );
+            }
+        }
+        assert_eq!(CoSlice::turn(0, Move::EVERY[20]), None);
+        assert_eq!(<(CornerOrientation, EO, UDSlice)>::position::<UDSlice>(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "isn't in the move table")]
+    fn missing_moves() {
+        // the phase 2 edges are only turned by domino moves
+        UDEdgePermutation::move_table().apply(0, R);
+    }
+
+    #[test]
+    fn pair_lookup() {
+        // any table works for checking the index, so skip generating one
+        type CoSlice = (CornerOrientation, UDSlice);
+        let entries: Vec<u8> = (0..CoSlice::MAX).map(|i| (i % 251) as u8).collect();
+        let table = PruningTable::<Cube, CoSlice>::new(entries.try_into().unwrap());
+        let h = Pair(table.as_ref());
+        for scramble in scrambles() {
+            let state = CoordCube::<(CornerOrientation, EO, UDSlice), 3>::new(&scramble);
+            assert_eq!(h.lower_bound(&state), table.as_ref().lower_bound(&scramble));
+        }
+    }
+
+    #[test]
+    fn coordinate_search() {
+        type Orientations = (CornerOrientation, EO);
+        let co = PruningTable::<Cube, CornerOrientation>::generate();
+        let eo = PruningTable::<Cube, EO>::generate();
+        let h = (Single(co.as_ref()), Single(eo.as_ref()));
+
+        for scramble in ["F", "F R B", "F U' R2 B L"] {
+            let scramble = scramble.parse::<Algorithm>().unwrap().to_cube();
+            let start = CoordCube::<Orientations, 2>::new(&scramble);
+            assert_eq!(
+                ida(start, 10, h).unwrap().len(),
+                ida(start, 10, ZeroBound).unwrap().len()
+            );
+        }
+
+        for scramble in scrambles() {
+            let solution = ida(CoordCube::<Orientations, 2>::new(&scramble), 10, h).unwrap();
+
+            // the solution orients every piece of the full cube
+            let oriented = scramble * Algorithm::from(solution).to_cube();
+            assert!(CoordCube::<Orientations, 2>::new(&oriented).is_solved());
+        }
+    }
+}
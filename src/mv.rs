@@ -8,10 +8,26 @@ pub enum Move {
     L, L2, L3,
     R, R2, R3,
     F, F2, F3,
-    B, B2, B3
+    B, B2, B3,
+    // wide turns, the face and the slice next to it
+    Uw, Uw2, Uw3,
+    Dw, Dw2, Dw3,
+    Lw, Lw2, Lw3,
+    Rw, Rw2, Rw3,
+    Fw, Fw2, Fw3,
+    Bw, Bw2, Bw3,
+    // slices, M follows L, E follows D, S follows F
+    M, M2, M3,
+    E, E2, E3,
+    S, S2, S3,
+    // whole cube rotations, x follows R, y follows U, z follows F
+    X, X2, X3,
+    Y, Y2, Y3,
+    Z, Z2, Z3,
 }
 
 impl Move {
+    /// the outer face turns, the moves searched in the half and quarter turn metrics
     pub const ALL: &[Self] = {
         use Move::*;
         &[
@@ -19,6 +35,35 @@ impl Move {
         ]
     };
 
+    /// the moves that keep the cube in the domino subgroup <U, D, R2, L2, F2, B2>, where every
+    /// piece is oriented and the E slice edges stay in the E slice
+    pub const DOMINO: &[Self] = {
+        use Move::*;
+        &[U, U2, U3, D, D2, D3, L2, R2, F2, B2]
+    };
+
+    /// the face and slice turns searched in the slice turn metric
+    #[rustfmt::skip]
+    pub const FACES_AND_SLICES: &[Self] = {
+        use Move::*;
+        &[
+            U, U2, U3, D, D2, D3, L, L2, L3, R, R2, R3, F, F2, F3, B, B2, B3,
+            M, M2, M3, E, E2, E3, S, S2, S3,
+        ]
+    };
+
+    /// every move that can be written in notation, in the same order as the enum
+    #[rustfmt::skip]
+    pub const EVERY: &[Self] = {
+        use Move::*;
+        &[
+            U, U2, U3, D, D2, D3, L, L2, L3, R, R2, R3, F, F2, F3, B, B2, B3,
+            Uw, Uw2, Uw3, Dw, Dw2, Dw3, Lw, Lw2, Lw3, Rw, Rw2, Rw3, Fw, Fw2, Fw3, Bw, Bw2, Bw3,
+            M, M2, M3, E, E2, E3, S, S2, S3,
+            X, X2, X3, Y, Y2, Y3, Z, Z2, Z3,
+        ]
+    };
+
     pub fn to_cube(self) -> Cube {
         use moves::*;
 
@@ -41,9 +86,49 @@ impl Move {
             Move::B => B,
             Move::B2 => B2,
             Move::B3 => BPRIME,
+            Move::M => M,
+            Move::M2 => M2,
+            Move::M3 => MPRIME,
+            Move::E => E,
+            Move::E2 => E2,
+            Move::E3 => EPRIME,
+            Move::S => S,
+            Move::S2 => S2,
+            Move::S3 => SPRIME,
+            _ => self.decompose().into_iter().map(Move::to_cube).product(),
         }
     }
 
+    /// wide turns and rotations written as the face and slice turns they're made of, every
+    /// other move is returned as is
+    pub fn decompose(self) -> Vec<Self> {
+        use Move::*;
+
+        // (layer, whether it turns the same way as the move)
+        let parts: &[(Move, bool)] = match self.layer() {
+            6 => &[(U, true), (E, false)],
+            7 => &[(D, true), (E, true)],
+            8 => &[(L, true), (M, true)],
+            9 => &[(R, true), (M, false)],
+            10 => &[(F, true), (S, true)],
+            11 => &[(B, true), (S, false)],
+            15 => &[(R, true), (M, false), (L, false)],
+            16 => &[(U, true), (E, false), (D, false)],
+            17 => &[(F, true), (S, true), (B, false)],
+            _ => return vec![self],
+        };
+
+        let turns = self.quarter_turns();
+        parts
+            .iter()
+            .map(|&(layer, same)| {
+                let turns = if same { turns } else { 4 - turns };
+                Move::from_layer(layer.layer(), turns).unwrap()
+            })
+            .collect()
+    }
+
+    #[rustfmt::skip]
     pub fn to_str(self) -> &'static str {
         match self {
             Move::U => "U",
@@ -64,46 +149,170 @@ impl Move {
             Move::B => "B",
             Move::B2 => "B2",
             Move::B3 => "B'",
+            Move::Uw => "Uw",
+            Move::Uw2 => "Uw2",
+            Move::Uw3 => "Uw'",
+            Move::Dw => "Dw",
+            Move::Dw2 => "Dw2",
+            Move::Dw3 => "Dw'",
+            Move::Lw => "Lw",
+            Move::Lw2 => "Lw2",
+            Move::Lw3 => "Lw'",
+            Move::Rw => "Rw",
+            Move::Rw2 => "Rw2",
+            Move::Rw3 => "Rw'",
+            Move::Fw => "Fw",
+            Move::Fw2 => "Fw2",
+            Move::Fw3 => "Fw'",
+            Move::Bw => "Bw",
+            Move::Bw2 => "Bw2",
+            Move::Bw3 => "Bw'",
+            Move::M => "M",
+            Move::M2 => "M2",
+            Move::M3 => "M'",
+            Move::E => "E",
+            Move::E2 => "E2",
+            Move::E3 => "E'",
+            Move::S => "S",
+            Move::S2 => "S2",
+            Move::S3 => "S'",
+            Move::X => "x",
+            Move::X2 => "x2",
+            Move::X3 => "x'",
+            Move::Y => "y",
+            Move::Y2 => "y2",
+            Move::Y3 => "y'",
+            Move::Z => "z",
+            Move::Z2 => "z2",
+            Move::Z3 => "z'",
         }
     }
 
-    pub fn cancels(self, other: Self) -> bool {
-        use Move::*;
-        #[rustfmt::skip]
-        match (self, other) {
-              (U, U3) | (U3, U) | (U2, U2)
-            | (D, D3) | (D3, D) | (D2, D2)
-            | (L, L3) | (L3, L) | (L2, L2)
-            | (R, R3) | (R3, R) | (R2, R2)
-            | (F, F3) | (F3, F) | (F2, F2)
-            | (B, B3) | (B3, B) | (B2, B2) => true,
-            _ => false,
+    /// index of the layers being turned: the faces U D L R F B are 0..6 with opposite faces
+    /// adjacent, then the wide turns in the same order, the slices M E S, and the rotations x y z
+    pub fn layer(self) -> u8 {
+        self as u8 / 3
+    }
+
+    /// moves on the same axis commute with each other
+    pub fn axis(self) -> u8 {
+        match self.layer() {
+            l @ 0..12 => l % 6 / 2,
+            // M, x
+            12 | 15 => 1,
+            // E, y
+            13 | 16 => 0,
+            // S, z
+            _ => 2,
         }
     }
 
-    #[rustfmt::skip]
+    pub fn is_face_turn(self) -> bool {
+        self.layer() < 6
+    }
+
+    pub fn is_wide_turn(self) -> bool {
+        (6..12).contains(&self.layer())
+    }
+
+    pub fn is_slice_turn(self) -> bool {
+        (12..15).contains(&self.layer())
+    }
+
+    pub fn is_rotation(self) -> bool {
+        self.layer() >= 15
+    }
+
+    /// number of clockwise quarter turns, 1..=3
+    pub fn quarter_turns(self) -> u8 {
+        self as u8 % 3 + 1
+    }
+
+    /// the move turning `layer` clockwise by `quarter_turns`, None if that is the identity
+    pub fn from_layer(layer: u8, quarter_turns: u8) -> Option<Self> {
+        match quarter_turns % 4 {
+            0 => None,
+            n => Some(Self::EVERY[(layer * 3 + n - 1) as usize]),
+        }
+    }
+
+    pub fn inverse(self) -> Self {
+        Self::from_layer(self.layer(), 4 - self.quarter_turns()).unwrap()
+    }
+
+    pub fn cost(self, metric: Metric) -> u8 {
+        let half = self.quarter_turns() == 2;
+
+        if self.is_rotation() {
+            return 0;
+        }
+
+        match metric {
+            Metric::Stm => 1,
+            // a slice is the two outer faces either side of it
+            Metric::Htm if self.is_slice_turn() => 2,
+            Metric::Htm => 1,
+            Metric::Qtm if self.is_slice_turn() => 2 + 2 * half as u8,
+            Metric::Qtm => 1 + half as u8,
+        }
+    }
+
+    pub fn cancels(self, other: Self) -> bool {
+        self.layer() == other.layer()
+            && (self.quarter_turns() + other.quarter_turns()).is_multiple_of(4)
+    }
+
     pub fn redundant(self, other: Self) -> bool {
-        use Move::*;
-        match (self, other) {
-              (U, U) | (U, U2) | (U, U3)
-            | (U2, U) | (U2, U2) | (U2, U3)
-            | (U3, U) | (U3, U2) | (U3, U3)
-            | (R, R) | (R, R2) | (R, R3)
-            | (R2, R) | (R2, R2) | (R2, R3)
-            | (R3, R) | (R3, R2) | (R3, R3)
-            | (F, F) | (F, F2) | (F, F3)
-            | (F2, F) | (F2, F2) | (F2, F3)
-            | (F3, F) | (F3, F2) | (F3, F3)
-            | (L, L) | (L, L2) | (L, L3)
-            | (L2, L) | (L2, L2) | (L2, L3)
-            | (L3, L) | (L3, L2) | (L3, L3)
-            | (D, D) | (D, D2) | (D, D3)
-            | (D2, D) | (D2, D2) | (D2, D3)
-            | (D3, D) | (D3, D2) | (D3, D3)
-            | (B, B) | (B, B2) | (B, B3)
-            | (B2, B) | (B2, B2) | (B2, B3)
-            | (B3, B) | (B3, B2) | (B3, B3) => true,
-            _ => false,
+        self.layer() == other.layer()
+    }
+
+    /// whether a search can skip this move right after `last`. Turning the same face twice in a
+    /// row is never optimal, and moves on opposite faces commute so only one order of them needs
+    /// searching
+    pub fn skip_after(self, last: Self) -> bool {
+        last.redundant(self) || (last.axis() == self.axis() && last.layer() > self.layer())
+    }
+}
+
+/// how the length of a move sequence is counted, rotations are free in every metric
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Metric {
+    /// half turn metric, every face turn counts as 1 and slices count as 2
+    Htm,
+    /// quarter turn metric, half turns count twice
+    Qtm,
+    /// slice turn metric, face and slice turns count as 1
+    Stm,
+}
+
+impl Metric {
+    /// the moves searched when optimising for this metric
+    pub fn moves(self) -> &'static [Move] {
+        match self {
+            Metric::Htm | Metric::Qtm => Move::ALL,
+            Metric::Stm => Move::FACES_AND_SLICES,
+        }
+    }
+
+    /// appended to the names of pruning table files, which hold different costs per metric
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Metric::Htm => "",
+            Metric::Qtm => "_qtm",
+            Metric::Stm => "_stm",
+        }
+    }
+}
+
+impl std::str::FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "htm" => Ok(Metric::Htm),
+            "qtm" => Ok(Metric::Qtm),
+            "stm" => Ok(Metric::Stm),
+            _ => Err(format!("unknown metric \"{s}\", expected htm, qtm or stm")),
         }
     }
 }
@@ -114,6 +323,95 @@ impl std::fmt::Display for Move {
     }
 }
 
+impl std::str::FromStr for Move {
+    type Err = ParseMoveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // longer prefixes first so "Rw" isn't read as "R" followed by "w"
+        #[rustfmt::skip]
+        const LAYERS: [(&str, u8); 24] = [
+            ("Uw", 6), ("Dw", 7), ("Lw", 8), ("Rw", 9), ("Fw", 10), ("Bw", 11),
+            ("U", 0), ("D", 1), ("L", 2), ("R", 3), ("F", 4), ("B", 5),
+            ("u", 6), ("d", 7), ("l", 8), ("r", 9), ("f", 10), ("b", 11),
+            ("M", 12), ("E", 13), ("S", 14),
+            ("x", 15), ("y", 16), ("z", 17),
+        ];
+
+        let err = || ParseMoveError(s.to_string());
+
+        let (layer, suffix) = LAYERS
+            .iter()
+            .find_map(|&(prefix, layer)| Some((layer, s.strip_prefix(prefix)?)))
+            .ok_or_else(err)?;
+
+        let turns = match suffix {
+            "" => 1,
+            "2" | "2'" => 2,
+            "'" | "3" => 3,
+            _ => return Err(err()),
+        };
+
+        Ok(Move::from_layer(layer, turns).unwrap())
+    }
+}
+
+/// a token that isn't a valid move
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseMoveError(pub String);
+
+impl std::fmt::Display for ParseMoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid move \"{}\"", self.0)
+    }
+}
+
+impl std::error::Error for ParseMoveError {}
+
+/// an invalid token in an algorithm, `position` is the byte offset of the token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseAlgorithmError {
+    pub position: usize,
+    pub token: String,
+}
+
+impl std::fmt::Display for ParseAlgorithmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid move \"{}\" at position {}",
+            self.token, self.position
+        )
+    }
+}
+
+impl std::error::Error for ParseAlgorithmError {}
+
+/// parses a sequence of moves like "R U2 F' L", moves can be separated by whitespace or commas
+pub fn parse_algorithm(s: &str) -> Result<Vec<Move>, ParseAlgorithmError> {
+    let is_separator = |c: char| c.is_whitespace() || c == ',';
+
+    let mut moves = Vec::new();
+    let mut rest = s;
+
+    loop {
+        rest = rest.trim_start_matches(is_separator);
+        if rest.is_empty() {
+            return Ok(moves);
+        }
+
+        let position = s.len() - rest.len();
+        let end = rest.find(is_separator).unwrap_or(rest.len());
+        let (token, remaining) = rest.split_at(end);
+
+        match token.parse() {
+            Ok(mv) => moves.push(mv),
+            Err(ParseMoveError(token)) => return Err(ParseAlgorithmError { position, token }),
+        }
+
+        rest = remaining;
+    }
+}
+
 impl std::ops::Mul<Move> for Move {
     type Output = Cube;
 
@@ -130,6 +428,7 @@ mod moves {
         ep: [3, 0, 1, 2, 4, 5, 6, 7, 8, 9, 10, 11],
         co: [0, 0, 0, 0, 0, 0, 0, 0],
         cp: [3, 0, 1, 2, 4, 5, 6, 7],
+        centers: [0, 1, 2, 3, 4, 5],
     };
 
     pub(super) const UPRIME: Cube = Cube {
@@ -137,6 +436,7 @@ mod moves {
         ep: [1, 2, 3, 0, 4, 5, 6, 7, 8, 9, 10, 11],
         co: [0, 0, 0, 0, 0, 0, 0, 0],
         cp: [1, 2, 3, 0, 4, 5, 6, 7],
+        centers: [0, 1, 2, 3, 4, 5],
     };
 
     // rotate the right face clockwise
@@ -145,6 +445,7 @@ mod moves {
         ep: [0, 6, 2, 3, 4, 1, 9, 7, 8, 5, 10, 11],
         co: [0, 2, 1, 0, 0, 1, 2, 0],
         cp: [0, 2, 6, 3, 4, 1, 5, 7],
+        centers: [0, 1, 2, 3, 4, 5],
     };
 
     pub(super) const RPRIME: Cube = Cube {
@@ -152,6 +453,7 @@ mod moves {
         ep: [0, 5, 2, 3, 4, 9, 1, 7, 8, 6, 10, 11],
         co: [0, 2, 1, 0, 0, 1, 2, 0],
         cp: [0, 5, 1, 3, 4, 6, 2, 7],
+        centers: [0, 1, 2, 3, 4, 5],
     };
 
     pub(super) const L: Cube = Cube {
@@ -159,6 +461,7 @@ mod moves {
         ep: [0, 1, 2, 4, 11, 5, 6, 3, 8, 9, 10, 7],
         co: [1, 0, 0, 2, 2, 0, 0, 1],
         cp: [4, 1, 2, 0, 7, 5, 6, 3],
+        centers: [0, 1, 2, 3, 4, 5],
     };
 
     pub(super) const LPRIME: Cube = Cube {
@@ -166,6 +469,7 @@ mod moves {
         ep: [0, 1, 2, 7, 3, 5, 6, 11, 8, 9, 10, 4],
         co: [1, 0, 0, 2, 2, 0, 0, 1],
         cp: [3, 1, 2, 7, 0, 5, 6, 4],
+        centers: [0, 1, 2, 3, 4, 5],
     };
 
     pub(super) const D: Cube = Cube {
@@ -173,6 +477,7 @@ mod moves {
         ep: [0, 1, 2, 3, 4, 5, 6, 7, 9, 10, 11, 8],
         co: [0, 0, 0, 0, 0, 0, 0, 0],
         cp: [0, 1, 2, 3, 5, 6, 7, 4],
+        centers: [0, 1, 2, 3, 4, 5],
     };
 
     pub(super) const DPRIME: Cube = Cube {
@@ -180,6 +485,7 @@ mod moves {
         ep: [0, 1, 2, 3, 4, 5, 6, 7, 11, 8, 9, 10],
         co: [0, 0, 0, 0, 0, 0, 0, 0],
         cp: [0, 1, 2, 3, 7, 4, 5, 6],
+        centers: [0, 1, 2, 3, 4, 5],
     };
 
     pub(super) const F: Cube = Cube {
@@ -187,6 +493,7 @@ mod moves {
         ep: [0, 1, 7, 3, 4, 5, 2, 10, 8, 9, 6, 11],
         co: [0, 0, 2, 1, 0, 0, 1, 2],
         cp: [0, 1, 3, 7, 4, 5, 2, 6],
+        centers: [0, 1, 2, 3, 4, 5],
     };
 
     pub(super) const FPRIME: Cube = Cube {
@@ -194,6 +501,7 @@ mod moves {
         ep: [0, 1, 6, 3, 4, 5, 10, 2, 8, 9, 7, 11],
         co: [0, 0, 2, 1, 0, 0, 1, 2],
         cp: [0, 1, 6, 2, 4, 5, 7, 3],
+        centers: [0, 1, 2, 3, 4, 5],
     };
 
     pub(super) const B: Cube = Cube {
@@ -201,6 +509,7 @@ mod moves {
         ep: [5, 1, 2, 3, 0, 8, 6, 7, 4, 9, 10, 11],
         co: [2, 1, 0, 0, 1, 2, 0, 0],
         cp: [1, 5, 2, 3, 0, 4, 6, 7],
+        centers: [0, 1, 2, 3, 4, 5],
     };
 
     pub(super) const BPRIME: Cube = Cube {
@@ -208,6 +517,7 @@ mod moves {
         ep: [4, 1, 2, 3, 8, 0, 6, 7, 5, 9, 10, 11],
         co: [2, 1, 0, 0, 1, 2, 0, 0],
         cp: [4, 0, 2, 3, 5, 1, 6, 7],
+        centers: [0, 1, 2, 3, 4, 5],
     };
 
     pub(super) const U2: Cube = Cube {
@@ -215,6 +525,7 @@ mod moves {
         ep: [2, 3, 0, 1, 4, 5, 6, 7, 8, 9, 10, 11],
         co: [0, 0, 0, 0, 0, 0, 0, 0],
         cp: [2, 3, 0, 1, 4, 5, 6, 7],
+        centers: [0, 1, 2, 3, 4, 5],
     };
 
     pub(super) const R2: Cube = Cube {
@@ -222,6 +533,7 @@ mod moves {
         ep: [0, 9, 2, 3, 4, 6, 5, 7, 8, 1, 10, 11],
         co: [0, 0, 0, 0, 0, 0, 0, 0],
         cp: [0, 6, 5, 3, 4, 2, 1, 7],
+        centers: [0, 1, 2, 3, 4, 5],
     };
 
     pub(super) const L2: Cube = Cube {
@@ -229,6 +541,7 @@ mod moves {
         ep: [0, 1, 2, 11, 7, 5, 6, 4, 8, 9, 10, 3],
         co: [0, 0, 0, 0, 0, 0, 0, 0],
         cp: [7, 1, 2, 4, 3, 5, 6, 0],
+        centers: [0, 1, 2, 3, 4, 5],
     };
 
     pub(super) const D2: Cube = Cube {
@@ -236,6 +549,7 @@ mod moves {
         ep: [0, 1, 2, 3, 4, 5, 6, 7, 10, 11, 8, 9],
         co: [0, 0, 0, 0, 0, 0, 0, 0],
         cp: [0, 1, 2, 3, 6, 7, 4, 5],
+        centers: [0, 1, 2, 3, 4, 5],
     };
 
     pub(super) const F2: Cube = Cube {
@@ -243,6 +557,7 @@ mod moves {
         ep: [0, 1, 10, 3, 4, 5, 7, 6, 8, 9, 2, 11],
         co: [0, 0, 0, 0, 0, 0, 0, 0],
         cp: [0, 1, 7, 6, 4, 5, 3, 2],
+        centers: [0, 1, 2, 3, 4, 5],
     };
 
     pub(super) const B2: Cube = Cube {
@@ -250,5 +565,163 @@ mod moves {
         ep: [8, 1, 2, 3, 5, 4, 6, 7, 0, 9, 10, 11],
         co: [0, 0, 0, 0, 0, 0, 0, 0],
         cp: [5, 4, 2, 3, 1, 0, 6, 7],
+        centers: [0, 1, 2, 3, 4, 5],
+    };
+
+    pub(super) const M: Cube = Cube {
+        eo: [1, 0, 1, 0, 0, 0, 0, 0, 1, 0, 1, 0],
+        ep: [8, 1, 0, 3, 4, 5, 6, 7, 10, 9, 2, 11],
+        co: [0, 0, 0, 0, 0, 0, 0, 0],
+        cp: [0, 1, 2, 3, 4, 5, 6, 7],
+        centers: [5, 4, 2, 3, 0, 1],
+    };
+
+    pub(super) const M2: Cube = Cube {
+        eo: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        ep: [10, 1, 8, 3, 4, 5, 6, 7, 2, 9, 0, 11],
+        co: [0, 0, 0, 0, 0, 0, 0, 0],
+        cp: [0, 1, 2, 3, 4, 5, 6, 7],
+        centers: [1, 0, 2, 3, 5, 4],
+    };
+
+    pub(super) const MPRIME: Cube = Cube {
+        eo: [1, 0, 1, 0, 0, 0, 0, 0, 1, 0, 1, 0],
+        ep: [2, 1, 10, 3, 4, 5, 6, 7, 0, 9, 8, 11],
+        co: [0, 0, 0, 0, 0, 0, 0, 0],
+        cp: [0, 1, 2, 3, 4, 5, 6, 7],
+        centers: [4, 5, 2, 3, 1, 0],
+    };
+
+    pub(super) const E: Cube = Cube {
+        eo: [0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0],
+        ep: [0, 1, 2, 3, 5, 6, 7, 4, 8, 9, 10, 11],
+        co: [0, 0, 0, 0, 0, 0, 0, 0],
+        cp: [0, 1, 2, 3, 4, 5, 6, 7],
+        centers: [0, 1, 5, 4, 2, 3],
+    };
+
+    pub(super) const E2: Cube = Cube {
+        eo: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        ep: [0, 1, 2, 3, 6, 7, 4, 5, 8, 9, 10, 11],
+        co: [0, 0, 0, 0, 0, 0, 0, 0],
+        cp: [0, 1, 2, 3, 4, 5, 6, 7],
+        centers: [0, 1, 3, 2, 5, 4],
+    };
+
+    pub(super) const EPRIME: Cube = Cube {
+        eo: [0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0],
+        ep: [0, 1, 2, 3, 7, 4, 5, 6, 8, 9, 10, 11],
+        co: [0, 0, 0, 0, 0, 0, 0, 0],
+        cp: [0, 1, 2, 3, 4, 5, 6, 7],
+        centers: [0, 1, 4, 5, 3, 2],
+    };
+
+    pub(super) const S: Cube = Cube {
+        eo: [0, 1, 0, 1, 0, 0, 0, 0, 0, 1, 0, 1],
+        ep: [0, 3, 2, 11, 4, 5, 6, 7, 8, 1, 10, 9],
+        co: [0, 0, 0, 0, 0, 0, 0, 0],
+        cp: [0, 1, 2, 3, 4, 5, 6, 7],
+        centers: [2, 3, 1, 0, 4, 5],
+    };
+
+    pub(super) const S2: Cube = Cube {
+        eo: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        ep: [0, 11, 2, 9, 4, 5, 6, 7, 8, 3, 10, 1],
+        co: [0, 0, 0, 0, 0, 0, 0, 0],
+        cp: [0, 1, 2, 3, 4, 5, 6, 7],
+        centers: [1, 0, 3, 2, 4, 5],
+    };
+
+    pub(super) const SPRIME: Cube = Cube {
+        eo: [0, 1, 0, 1, 0, 0, 0, 0, 0, 1, 0, 1],
+        ep: [0, 9, 2, 1, 4, 5, 6, 7, 8, 11, 10, 3],
+        co: [0, 0, 0, 0, 0, 0, 0, 0],
+        cp: [0, 1, 2, 3, 4, 5, 6, 7],
+        centers: [3, 2, 0, 1, 4, 5],
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Move::*;
+    use super::*;
+
+    #[test]
+    fn skip_after() {
+        assert!(R.skip_after(R) && U2.skip_after(U3));
+        assert!(!R.skip_after(U) && !U.skip_after(R));
+        // opposite faces are searched in one order
+        assert!(R.skip_after(L) != L.skip_after(R));
+        assert!(U2.skip_after(D) != D.skip_after(U2));
+    }
+
+    #[test]
+    fn parse_moves() {
+        #[rustfmt::skip]
+        let pairs = [
+            ("U", U), ("R2", R2), ("F'", F3), ("B3", B3), ("L2'", L2),
+            ("Rw", Rw), ("r'", Rw3), ("Uw2", Uw2), ("M'", M3), ("S", S), ("x", X), ("y2", Y2),
+        ];
+        for (s, mv) in pairs {
+            assert_eq!(s.parse::<Move>(), Ok(mv));
+        }
+
+        for s in ["", "Q", "R4", "Rw'2", "R''", "RU", "X", "w"] {
+            assert_eq!(s.parse::<Move>(), Err(ParseMoveError(s.to_string())));
+        }
+    }
+
+    #[test]
+    fn parse_separators() {
+        assert_eq!(
+            parse_algorithm("  R U2,F'\tL ,, B\nD R "),
+            Ok(vec![R, U2, F3, L, B, D, R])
+        );
+        assert_eq!(parse_algorithm(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn parse_error_position() {
+        assert_eq!(
+            parse_algorithm("R U2, Fx L"),
+            Err(ParseAlgorithmError {
+                position: 6,
+                token: "Fx".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn layers() {
+        for &mv in Move::EVERY {
+            assert_eq!(Move::from_layer(mv.layer(), mv.quarter_turns()), Some(mv));
+            assert!(mv.cancels(mv.inverse()));
+        }
+        assert_eq!(Move::from_layer(R.layer(), 4), None);
+    }
+
+    #[test]
+    fn decompositions() {
+        let pairs = [
+            (Rw, vec![R, M3]),
+            (Uw2, vec![U2, E2]),
+            (Fw3, vec![F3, S3]),
+            (X3, vec![R3, M, L]),
+            (Z, vec![F, S, B3]),
+        ];
+        for (mv, parts) in pairs {
+            assert_eq!(mv.decompose(), parts);
+        }
+    }
+
+    #[test]
+    fn to_str_round_trip() {
+        let printed = Move::EVERY
+            .iter()
+            .map(|m| m.to_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        assert_eq!(parse_algorithm(&printed).as_deref(), Ok(Move::EVERY));
+    }
+}
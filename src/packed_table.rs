@@ -0,0 +1,296 @@
+use std::marker::PhantomData;
+
+use crate::heuristics::Heuristic;
+use crate::mv::{Metric, Move};
+use crate::pruning_table::{Coordinate, PruningTable, generating_moves};
+use crate::puzzle::Puzzle;
+
+/// how the entries of a pruning table are stored
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// a byte per entry, `PruningTable`
+    Byte,
+    /// 4 bits per entry, `NibblePruningTable`
+    Nibble,
+    /// 2 bits per entry, `Mod3PruningTable`
+    Mod3,
+}
+
+impl Encoding {
+    /// how the encoding is written on the command line and in table file headers
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Byte => "byte",
+            Encoding::Nibble => "nibble",
+            Encoding::Mod3 => "mod3",
+        }
+    }
+}
+
+impl std::str::FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "byte" => Ok(Encoding::Byte),
+            "nibble" => Ok(Encoding::Nibble),
+            "mod3" => Ok(Encoding::Mod3),
+            _ => Err(format!(
+                "unknown encoding \"{s}\", expected byte, nibble or mod3"
+            )),
+        }
+    }
+}
+
+/// a pruning table stored in fewer bits per entry, packed from a full `PruningTable`
+pub trait PackedTable<S, T: Coordinate<S>>: Sized {
+    /// appended to the names of the table files, after the metric's suffix
+    const SUFFIX: &'static str;
+    const ENCODING: Encoding;
+
+    fn pack(table: &PruningTable<S, T>, metric: Metric) -> Self
+    where
+        [u8; T::MAX]: Sized;
+
+    /// `None` when `bytes` is the wrong length for the coordinate
+    fn from_bytes(bytes: Vec<u8>, metric: Metric) -> Option<Self>;
+
+    fn as_bytes(&self) -> &[u8];
+}
+
+/// two entries per byte, costs above 15 are stored as 15 so it's still a lower bound
+pub struct NibblePruningTable<S, T> {
+    data: Box<[u8]>,
+    coordinate: PhantomData<fn() -> (S, T)>,
+}
+
+impl<S, T: Coordinate<S>> NibblePruningTable<S, T> {
+    fn get(&self, index: usize) -> u8 {
+        (self.data[index / 2] >> (4 * (index % 2))) & 0xF
+    }
+}
+
+impl<S, T: Coordinate<S>> PackedTable<S, T> for NibblePruningTable<S, T> {
+    const SUFFIX: &'static str = "_nibble";
+    const ENCODING: Encoding = Encoding::Nibble;
+
+    fn pack(table: &PruningTable<S, T>, _metric: Metric) -> Self
+    where
+        [u8; T::MAX]: Sized,
+    {
+        let mut data = vec![0; T::MAX.div_ceil(2)];
+        for (index, &cost) in table.as_ref().iter().enumerate() {
+            data[index / 2] |= cost.min(0xF) << (4 * (index % 2));
+        }
+
+        NibblePruningTable {
+            data: data.into(),
+            coordinate: PhantomData,
+        }
+    }
+
+    fn from_bytes(bytes: Vec<u8>, _metric: Metric) -> Option<Self> {
+        (bytes.len() == T::MAX.div_ceil(2)).then(|| NibblePruningTable {
+            data: bytes.into(),
+            coordinate: PhantomData,
+        })
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl<S, T: Coordinate<S>> Heuristic<S> for &NibblePruningTable<S, T> {
+    type Memo = u8;
+
+    fn lower_bound(self, state: &S) -> u8 {
+        self.get(T::to_coord(state))
+    }
+
+    fn memo(self, state: &S) -> u8 {
+        self.lower_bound(state)
+    }
+
+    fn next_memo(self, state: &S, _parent: u8) -> u8 {
+        self.lower_bound(state)
+    }
+
+    fn memo_bound(self, memo: u8) -> u8 {
+        memo
+    }
+}
+
+/// four entries per byte holding the cost mod 3. A state's neighbours are at most one move
+/// closer or further, so the exact cost is recovered from the neighbour the search came from,
+/// and from scratch by following the moves that get closer back to solved. That only works when
+/// every move costs 1, so tables for the quarter turn metric can't be stored this way, and the
+/// search must only use moves the table was generated with
+pub struct Mod3PruningTable<S, T> {
+    data: Box<[u8]>,
+    metric: Metric,
+    coordinate: PhantomData<fn() -> (S, T)>,
+}
+
+/// stored for entries the table's moves never reach
+const UNREACHABLE: u8 = 3;
+
+impl<S, T: Coordinate<S>> Mod3PruningTable<S, T> {
+    fn get(&self, index: usize) -> u8 {
+        (self.data[index / 4] >> (2 * (index % 4))) & 0b11
+    }
+}
+
+impl<S, T> Mod3PruningTable<S, T>
+where
+    S: Puzzle + Clone + std::ops::Mul<Move, Output = S>,
+    T: Coordinate<S>,
+{
+    /// the exact cost of solving `state`, found by walking back to solved
+    fn cost(&self, state: &S) -> u8 {
+        let solved = T::to_coord(&S::solved());
+        let mut state = state.clone();
+        let mut cost = 0;
+
+        loop {
+            let index = T::to_coord(&state);
+            if index == solved {
+                return cost;
+            }
+
+            let stored = self.get(index);
+            if stored == UNREACHABLE {
+                return u8::MAX;
+            }
+
+            // every entry apart from solved has a neighbour one move closer
+            state = generating_moves::<S, T>(self.metric)
+                .iter()
+                .map(|&mv| (state.clone() * mv).reoriented())
+                .find(|next| self.get(T::to_coord(next)) == (stored + 2) % 3)
+                .expect("pruning table entries should have a neighbour closer to solved");
+            cost += 1;
+        }
+    }
+}
+
+impl<S, T: Coordinate<S>> PackedTable<S, T> for Mod3PruningTable<S, T> {
+    const SUFFIX: &'static str = "_mod3";
+    const ENCODING: Encoding = Encoding::Mod3;
+
+    fn pack(table: &PruningTable<S, T>, metric: Metric) -> Self
+    where
+        [u8; T::MAX]: Sized,
+    {
+        assert!(
+            metric != Metric::Qtm,
+            "mod 3 tables need every move to cost 1"
+        );
+
+        let mut data = vec![0; T::MAX.div_ceil(4)];
+        for (index, &cost) in table.as_ref().iter().enumerate() {
+            let stored = if cost == u8::MAX {
+                UNREACHABLE
+            } else {
+                cost % 3
+            };
+            data[index / 4] |= stored << (2 * (index % 4));
+        }
+
+        Mod3PruningTable {
+            data: data.into(),
+            metric,
+            coordinate: PhantomData,
+        }
+    }
+
+    fn from_bytes(bytes: Vec<u8>, metric: Metric) -> Option<Self> {
+        (bytes.len() == T::MAX.div_ceil(4)).then(|| Mod3PruningTable {
+            data: bytes.into(),
+            metric,
+            coordinate: PhantomData,
+        })
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl<S, T> Heuristic<S> for &Mod3PruningTable<S, T>
+where
+    S: Puzzle + Clone + std::ops::Mul<Move, Output = S>,
+    T: Coordinate<S>,
+{
+    /// the exact cost
+    type Memo = u8;
+
+    fn lower_bound(self, state: &S) -> u8 {
+        self.cost(state)
+    }
+
+    fn memo(self, state: &S) -> u8 {
+        self.cost(state)
+    }
+
+    fn next_memo(self, state: &S, parent: u8) -> u8 {
+        let stored = self.get(T::to_coord(state));
+        if stored == UNREACHABLE {
+            return u8::MAX;
+        }
+        if parent == u8::MAX {
+            return self.cost(state);
+        }
+
+        (parent.saturating_sub(1)..=parent + 1)
+            .find(|cost| cost % 3 == stored)
+            .unwrap()
+    }
+
+    fn memo_bound(self, memo: u8) -> u8 {
+        memo
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::{Cube, SOLVED};
+    use crate::mv::Move::*;
+    use crate::pruning_table::{CornerOrientation, EO};
+
+    #[test]
+    fn packed_tables_match() {
+        let table = PruningTable::<Cube, EO>::generate();
+        let nibbles = NibblePruningTable::pack(&table, Metric::Htm);
+        let mod3 = Mod3PruningTable::pack(&table, Metric::Htm);
+        assert_eq!(nibbles.as_bytes().len(), 1024);
+        assert_eq!(mod3.as_bytes().len(), 512);
+
+        let mut cube = SOLVED;
+        let mut memo = (&mod3).memo(&cube);
+        for mv in [F, R, B3, U, L2, F, D, B] {
+            cube = cube * mv;
+            memo = (&mod3).next_memo(&cube, memo);
+
+            let cost = table.as_ref().lower_bound(&cube);
+            assert_eq!((&nibbles).lower_bound(&cube), cost);
+            assert_eq!((&mod3).lower_bound(&cube), cost);
+            assert_eq!(memo, cost);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let table = PruningTable::<Cube, CornerOrientation>::generate();
+        let mod3 = Mod3PruningTable::pack(&table, Metric::Htm);
+        let bytes = mod3.as_bytes().to_vec();
+
+        let loaded = Mod3PruningTable::<Cube, CornerOrientation>::from_bytes(bytes, Metric::Htm);
+        assert_eq!(loaded.unwrap().as_bytes(), mod3.as_bytes());
+        assert!(
+            NibblePruningTable::<Cube, CornerOrientation>::from_bytes(vec![0; 3], Metric::Htm)
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,409 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::Instant;
+
+use crate::heuristics::Heuristic;
+use crate::mv::{Metric, Move};
+use crate::progress::progress;
+use crate::puzzle::Puzzle;
+use crate::search::{
+    AbortReason, CHECK_INTERVAL, Heartbeats, SearchEvent, SearchLimits, SearchOutcome, SearchResult,
+};
+
+/// how many moves from the start are split off into separate subtrees
+const SPLIT_DEPTH: u8 = 2;
+
+/// `ida` spread over `threads` threads. Each depth is split into the subtrees below the first two
+/// moves, idle threads take the next unsearched subtree so unbalanced ones don't leave threads
+/// waiting, and all threads stop as soon as any of them finds a solution. Like
+/// `ida_with_metric`, the solution is optimal in `metric`. The search gives up once it reaches
+/// one of the `limits`, and sends their observer its events. At least one thread is used
+pub fn par_ida<T, H>(
+    puzzle: T,
+    max_cost: u8,
+    metric: Metric,
+    threads: usize,
+    limits: &SearchLimits,
+    h: H,
+) -> SearchResult
+where
+    T: Puzzle + Clone + Send + Sync,
+    H: Heuristic<T> + Send + Sync,
+    for<'a> &'a T: std::ops::Mul<Move, Output = T>,
+{
+    // a rotated puzzle is solved once it's solved relative to its centers
+    let puzzle = puzzle.reoriented();
+    let threads = threads.max(1);
+    let heartbeats = Heartbeats::new(limits);
+    let total = AtomicU64::new(0);
+    let first = h.memo_bound(h.memo(&puzzle));
+    let mut lower_bound = first;
+
+    for depth in first..=max_cost {
+        if let Some(reason) = limits.check(total.load(Ordering::Relaxed)) {
+            return SearchResult {
+                outcome: SearchOutcome::Aborted(reason),
+                nodes: total.into_inner(),
+                lower_bound,
+            };
+        }
+
+        progress!("starting depth {depth} on {threads} threads...");
+        limits.send(|| SearchEvent::DepthStarted { depth });
+        let start = Instant::now();
+        let before = total.load(Ordering::Relaxed);
+
+        let mut subtrees = Vec::new();
+        let mut split_nodes = 0;
+        split(
+            &puzzle,
+            &mut Vec::new(),
+            depth,
+            metric,
+            &mut subtrees,
+            &mut split_nodes,
+        );
+        total.fetch_add(split_nodes, Ordering::Relaxed);
+        let branches = AtomicU64::new(split_nodes);
+
+        let next = AtomicUsize::new(0);
+        let stop = AtomicBool::new(false);
+        let aborted = Mutex::new(None);
+        let solution = Mutex::new(None);
+
+        std::thread::scope(|s| {
+            for _ in 0..threads {
+                s.spawn(|| {
+                    let mut search = Search {
+                        h,
+                        metric,
+                        limits,
+                        stop: &stop,
+                        aborted: &aborted,
+                        total: &total,
+                        heartbeats: &heartbeats,
+                        nodes: 0,
+                        branches: 0,
+                    };
+
+                    while !stop.load(Ordering::Relaxed) {
+                        let Some((state, path)) =
+                            subtrees.get(next.fetch_add(1, Ordering::Relaxed))
+                        else {
+                            break;
+                        };
+
+                        let (mut path, cost) = (path.clone(), cost(path, metric));
+                        let memo = h.memo(state);
+                        if search.dfs(state, memo, &mut path, depth - cost) {
+                            stop.store(true, Ordering::Relaxed);
+                            solution.lock().unwrap().get_or_insert(path);
+                        }
+                    }
+
+                    // the nodes since the last check
+                    total.fetch_add(search.nodes % CHECK_INTERVAL, Ordering::Relaxed);
+                    branches.fetch_add(search.branches, Ordering::Relaxed);
+                });
+            }
+        });
+
+        let elapsed = start.elapsed();
+        let nodes = total.load(Ordering::Relaxed) - before;
+        let branching_factor = match branches.into_inner() {
+            0 => 0.0,
+            branches => (nodes - 1) as f64 / branches as f64,
+        };
+        progress!(
+            "searched {} nodes in {:.2?} at {:.2}M nodes/s, branching factor: {:.2}",
+            nodes,
+            elapsed,
+            nodes as f64 / elapsed.as_secs_f64() / 1_000_000.0,
+            branching_factor
+        );
+
+        if let Some(path) = solution.into_inner().unwrap() {
+            limits.send(|| SearchEvent::SolutionFound {
+                solution: path.clone(),
+                nodes: total.load(Ordering::Relaxed),
+                elapsed: heartbeats.elapsed(),
+            });
+            return SearchResult {
+                outcome: SearchOutcome::Solved(path),
+                nodes: total.into_inner(),
+                lower_bound: depth,
+            };
+        }
+        if let Some(reason) = aborted.into_inner().unwrap() {
+            return SearchResult {
+                outcome: SearchOutcome::Aborted(reason),
+                nodes: total.into_inner(),
+                lower_bound,
+            };
+        }
+        limits.send(|| SearchEvent::DepthFinished {
+            depth,
+            nodes,
+            elapsed,
+            branching_factor,
+        });
+        lower_bound = depth + 1;
+    }
+
+    SearchResult {
+        outcome: SearchOutcome::NoSolution,
+        nodes: total.into_inner(),
+        lower_bound,
+    }
+}
+
+/// every state `SPLIT_DEPTH` moves from `puzzle`, or fewer if there's no budget left for another
+/// move, along with the moves that lead to it. `nodes` counts the states that were split
+fn split<T: Puzzle + Clone>(
+    puzzle: &T,
+    path: &mut Vec<Move>,
+    remaining: u8,
+    metric: Metric,
+    subtrees: &mut Vec<(T, Vec<Move>)>,
+    nodes: &mut u64,
+) where
+    for<'a> &'a T: std::ops::Mul<Move, Output = T>,
+{
+    if remaining == 0 || path.len() as u8 == SPLIT_DEPTH {
+        subtrees.push((puzzle.clone(), path.clone()));
+        return;
+    }
+
+    *nodes += 1;
+    for &mv in metric.moves() {
+        if mv.cost(metric) > remaining || path.last().is_some_and(|&last| mv.skip_after(last)) {
+            continue;
+        }
+
+        path.push(mv);
+        let puzzle = (puzzle * mv).reoriented();
+        split(
+            &puzzle,
+            path,
+            remaining - mv.cost(metric),
+            metric,
+            subtrees,
+            nodes,
+        );
+        path.pop();
+    }
+}
+
+fn cost(path: &[Move], metric: Metric) -> u8 {
+    path.iter().map(|mv| mv.cost(metric)).sum()
+}
+
+struct Search<'a, H> {
+    h: H,
+    metric: Metric,
+    limits: &'a SearchLimits,
+    /// set once a thread finds a solution or reaches a limit
+    stop: &'a AtomicBool,
+    aborted: &'a Mutex<Option<AbortReason>>,
+    /// the nodes every thread has searched at every depth, added to every `CHECK_INTERVAL` nodes
+    total: &'a AtomicU64,
+    heartbeats: &'a Heartbeats,
+    nodes: u64,
+    /// the nodes this thread has searched below, at this depth
+    branches: u64,
+}
+
+impl<H> Search<'_, H> {
+    /// leaves the solution in `path` when one is found
+    fn dfs<T: Puzzle>(
+        &mut self,
+        puzzle: &T,
+        memo: H::Memo,
+        path: &mut Vec<Move>,
+        remaining: u8,
+    ) -> bool
+    where
+        H: Heuristic<T>,
+        for<'a> &'a T: std::ops::Mul<Move, Output = T>,
+    {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            let total = self.total.fetch_add(CHECK_INTERVAL, Ordering::Relaxed) + CHECK_INTERVAL;
+            if let Some(reason) = self.limits.check(total) {
+                self.aborted.lock().unwrap().get_or_insert(reason);
+                self.stop.store(true, Ordering::Relaxed);
+            }
+            self.heartbeats.tick(self.limits, total, path);
+        }
+
+        if remaining == 0 {
+            return puzzle.is_solved();
+        }
+
+        if self.h.memo_bound(memo) > remaining || self.stop.load(Ordering::Relaxed) {
+            return false;
+        }
+        self.branches += 1;
+
+        for &mv in self.metric.moves() {
+            let cost = mv.cost(self.metric);
+            if cost > remaining || path.last().is_some_and(|&last| mv.skip_after(last)) {
+                continue;
+            }
+
+            path.push(mv);
+            let next = (puzzle * mv).reoriented();
+            let memo = self.h.next_memo(&next, memo);
+            if self.dfs(&next, memo, path, remaining - cost) {
+                return true;
+            }
+            path.pop();
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::Algorithm;
+    use crate::cube::ida;
+    use crate::heuristics::ZeroBound;
+    use crate::search::{CancelToken, Observer};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn finds_optimal_solutions() {
+        for scramble in ["", "R", "R L", "U R2 F", "F' L D2"] {
+            let cube = scramble.parse::<Algorithm>().unwrap().to_cube();
+
+            let limits = SearchLimits::default();
+            let result = par_ida(cube.clone(), 5, Metric::Htm, 4, &limits, ZeroBound);
+            let solution = result.into_solution().unwrap();
+            assert_eq!(
+                solution.len(),
+                ida(cube.clone(), 5, ZeroBound).unwrap().len()
+            );
+            assert!((cube.clone() * Algorithm::from(solution).to_cube()).is_solved());
+
+            // no threads is taken as one
+            let result = par_ida(cube, 5, Metric::Htm, 0, &limits, ZeroBound);
+            assert!(result.solution().is_some());
+        }
+    }
+
+    #[test]
+    fn stops_at_limits() {
+        let cube = "R U F D2 L B' U R2 F D' L2 B"
+            .parse::<Algorithm>()
+            .unwrap()
+            .to_cube();
+        let search =
+            |limits: &SearchLimits| par_ida(cube.clone(), 20, Metric::Htm, 2, limits, ZeroBound);
+
+        let start = Instant::now();
+        let result = search(&SearchLimits::default().deadline(start));
+        assert_eq!(
+            result.outcome,
+            SearchOutcome::Aborted(AbortReason::Deadline)
+        );
+        assert_eq!((result.nodes, result.lower_bound), (0, 0));
+
+        // without a bound this would take far longer than the deadline
+        let result = search(&SearchLimits::default().deadline(start + Duration::from_millis(50)));
+        assert_eq!(
+            result.outcome,
+            SearchOutcome::Aborted(AbortReason::Deadline)
+        );
+        assert!(result.nodes > 0 && result.lower_bound >= 4);
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        // depths 0 to 4 take about 50,000 nodes between them, so the search stops during depth 5
+        let result = search(&SearchLimits::default().max_nodes(100_000));
+        assert_eq!(result.outcome, SearchOutcome::Aborted(AbortReason::Nodes));
+        assert!((100_000..100_000 + 2 * CHECK_INTERVAL).contains(&result.nodes));
+        assert_eq!(result.lower_bound, 5);
+
+        let token = CancelToken::new();
+        let cancel = token.clone();
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            cancel.cancel();
+        });
+        let result = search(&SearchLimits::default().cancel(token));
+        canceller.join().unwrap();
+        assert_eq!(
+            result.outcome,
+            SearchOutcome::Aborted(AbortReason::Cancelled)
+        );
+
+        let result = par_ida(cube, 3, Metric::Htm, 2, &SearchLimits::default(), ZeroBound);
+        assert_eq!(
+            (result.outcome, result.lower_bound),
+            (SearchOutcome::NoSolution, 4)
+        );
+    }
+
+    #[test]
+    fn sends_events() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let observer = Observer::new(move |event| sink.lock().unwrap().push(event.clone()))
+            .heartbeat(Duration::ZERO);
+        let limits = SearchLimits::default().observer(observer);
+
+        // every depth up to 4 is searched, about 50,000 nodes
+        let cube = "R U F D2 L B'".parse::<Algorithm>().unwrap().to_cube();
+        par_ida(cube, 4, Metric::Htm, 2, &limits, ZeroBound);
+        let depths: Vec<_> = events
+            .lock()
+            .unwrap()
+            .drain(..)
+            .filter_map(|event| match event {
+                SearchEvent::DepthStarted { depth } => Some((depth, None)),
+                SearchEvent::DepthFinished {
+                    depth,
+                    nodes,
+                    branching_factor,
+                    ..
+                } => Some((depth, Some((nodes, branching_factor.round())))),
+                SearchEvent::Heartbeat { nodes, .. } => {
+                    assert!(nodes > 0);
+                    None
+                }
+                SearchEvent::SolutionFound { .. } => panic!("there's no solution"),
+            })
+            .collect();
+        // 18 moves from the first node, then 15 or 12 from the rest, which don't turn the last
+        // face again or its opposite face in the other order
+        assert_eq!(
+            depths,
+            [
+                (0, None),
+                (0, Some((1, 0.0))),
+                (1, None),
+                (1, Some((19, 18.0))),
+                (2, None),
+                (2, Some((262, 14.0))),
+                (3, None),
+                (3, Some((3502, 13.0))),
+                (4, None),
+                (4, Some((46756, 13.0))),
+            ]
+        );
+
+        let cube = "R U".parse::<Algorithm>().unwrap().to_cube();
+        let result = par_ida(cube, 4, Metric::Htm, 2, &limits, ZeroBound);
+        match events.lock().unwrap().last() {
+            Some(SearchEvent::SolutionFound {
+                solution, nodes, ..
+            }) => {
+                assert_eq!(Some(&solution[..]), result.solution());
+                assert_eq!(*nodes, result.nodes);
+            }
+            event => panic!("{event:?}"),
+        }
+    }
+}
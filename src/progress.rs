@@ -0,0 +1,62 @@
+use std::cell::RefCell;
+use std::sync::Arc;
+
+/// receives the diagnostics of table generation and searches, one line at a time
+pub type Progress = Arc<dyn Fn(&str) + Send + Sync>;
+
+thread_local! {
+    static CALLBACK: RefCell<Option<Progress>> = const { RefCell::new(None) };
+}
+
+/// runs `f` with diagnostics on this thread going to `callback` instead of stderr. Searches and
+/// table generation only report from the thread that started them, so their helper threads don't
+/// need it set
+pub fn with_progress<R>(callback: Progress, f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<Progress>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            CALLBACK.with(|cb| *cb.borrow_mut() = self.0.take());
+        }
+    }
+
+    let _restore = Restore(CALLBACK.with(|cb| cb.borrow_mut().replace(callback)));
+    f()
+}
+
+/// sends a line to the callback set by `with_progress`, or to stderr without one
+pub fn report(args: std::fmt::Arguments) {
+    match CALLBACK.with(|cb| cb.borrow().clone()) {
+        Some(callback) => callback(&args.to_string()),
+        None => eprintln!("{args}"),
+    }
+}
+
+/// `eprintln!` for diagnostics that a library user might want somewhere else
+#[macro_export]
+macro_rules! progress {
+    ($($arg:tt)*) => {
+        $crate::progress::report(format_args!($($arg)*))
+    };
+}
+pub use progress;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn callback_is_scoped() {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let sink = lines.clone();
+        let callback: Progress = Arc::new(move |line| sink.lock().unwrap().push(line.to_string()));
+
+        with_progress(callback.clone(), || {
+            progress!("depth {}", 1);
+            with_progress(Arc::new(|_| {}), || progress!("dropped"));
+            progress!("depth {}", 2);
+        });
+        assert_eq!(*lines.lock().unwrap(), ["depth 1", "depth 2"]);
+        assert!(CALLBACK.with(|cb| cb.borrow().is_none()));
+    }
+}
@@ -1,7 +1,11 @@
 use std::iter::zip;
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
 
-use crate::cube::Cube;
+use crate::cube::{Cube, parity};
 use crate::heuristics::Heuristic;
+use crate::move_table::MoveCoordinate;
+use crate::mv::{Metric, Move};
+use crate::progress::progress;
 use crate::puzzle::Puzzle;
 
 // S = puzzle, T = coordinate
@@ -10,10 +14,24 @@ where
     [u8; T::MAX]: Sized,
     T: Coordinate<S>,
 {
+    type Memo = u8;
+
     fn lower_bound(self, state: &S) -> u8 {
         let index = T::to_coord(state);
         self.0[index]
     }
+
+    fn memo(self, state: &S) -> u8 {
+        self.lower_bound(state)
+    }
+
+    fn next_memo(self, state: &S, _parent: u8) -> u8 {
+        self.lower_bound(state)
+    }
+
+    fn memo_bound(self, memo: u8) -> u8 {
+        memo
+    }
 }
 
 impl<S, T: Coordinate<S>> AsRef<[u8]> for PruningTable<S, T>
@@ -30,64 +48,110 @@ pub struct PruningTable<S, T: Coordinate<S>>([u8; T::MAX])
 where
     [u8; T::MAX]: Sized;
 
-impl<S: Puzzle + Clone + std::ops::Mul<crate::mv::Move, Output = S>, T: Coordinate<S>>
-    PruningTable<S, T>
+impl<S: Puzzle + Clone + std::ops::Mul<Move, Output = S>, T: Coordinate<S>> PruningTable<S, T>
 where
     [u8; T::MAX]: Sized,
 {
     pub fn generate() -> Box<Self> {
-        eprintln!(
-            "Generating pruning table for {}",
-            std::any::type_name::<T>()
-        );
+        Self::generate_for(Metric::Htm)
+    }
 
-        let mut table: Box<[u8; T::MAX]> = vec![0; T::MAX].try_into().unwrap();
+    /// fills in the cost of solving every coordinate in `metric`. Coordinates generated with
+    /// `T::MOVES` as `Move::ALL` use the moves of the metric, in the slice turn metric that means
+    /// the coordinate has to be well defined after the cube is reoriented, like the full corner
+    /// coordinate
+    pub fn generate_for(metric: Metric) -> Box<Self> {
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        Self::generate_with(metric, threads)
+    }
 
-        let start = std::time::Instant::now();
+    /// `generate_for` split over `threads` threads. Each depth either expands the entries at the
+    /// current cost, or once more than half the table is filled, looks for a neighbour at the
+    /// current cost from each of the unfilled entries, since there are fewer of them left to check
+    /// and most have a neighbour that ends the check early
+    pub fn generate_with(metric: Metric, threads: usize) -> Box<Self> {
+        Self::generate_chunked(metric, threads, CHUNK)
+    }
 
-        let mut total_filled = 1;
+    /// `generate_with` with the threads taking `chunk` entries at a time
+    fn generate_chunked(metric: Metric, threads: usize, chunk: usize) -> Box<Self> {
+        progress!(
+            "Generating {metric:?} pruning table for {} on {threads} threads",
+            std::any::type_name::<T>()
+        );
 
-        // all the face turns can be set to 1
-        for &mv in crate::mv::Move::ALL {
-            let new_state = S::solved() * mv;
-            let index = T::to_coord(&new_state);
-            table[index] = 1;
-            total_filled += 1;
-        }
+        let mut table: Box<[u8; T::MAX]> = vec![UNSET; T::MAX].try_into().unwrap();
 
-        'depth: for depth in 2.. {
-            eprintln!("Generating depth {depth}, {total_filled} filled");
-            for index in 1..T::MAX {
-                if total_filled >= T::MAX {
-                    eprintln!("Filled all entries in the table, stopping at depth {depth}");
-                    break 'depth;
-                }
-                let moves_to_solve = table[index];
+        let start = std::time::Instant::now();
 
-                if moves_to_solve != depth - 1 {
-                    continue;
+        let moves = generating_moves::<S, T>(metric);
+        // how much further than the furthest entry the next entry could be
+        let reach = moves.iter().map(|mv| mv.cost(metric)).max().unwrap_or(1);
+
+        table[T::to_coord(&S::solved())] = 0;
+        let filled = AtomicUsize::new(1);
+        let highest = AtomicU8::new(0);
+        // AtomicU8 has the same size and alignment as u8, and the box isn't used while this exists
+        let entries = unsafe { &*(table.as_mut_slice() as *mut [u8] as *const [AtomicU8]) };
+
+        // moves cost 1 or 2, so every entry at the current cost is final once all the cheaper
+        // entries have been expanded
+        for cost in 0.. {
+            let total_filled = filled.load(Ordering::Relaxed);
+            let backward = total_filled > T::MAX / 2;
+            progress!(
+                "Generating depth {}, {total_filled} filled{}",
+                cost + 1,
+                if backward { ", searching backward" } else { "" }
+            );
+
+            let next = AtomicUsize::new(0);
+            std::thread::scope(|s| {
+                for _ in 0..threads {
+                    s.spawn(|| {
+                        let sweep = Sweep {
+                            entries,
+                            moves,
+                            metric,
+                            cost,
+                            filled: &filled,
+                            highest: &highest,
+                        };
+
+                        // small chunks so threads that get cheap chunks take more of them
+                        loop {
+                            let first = next.fetch_add(chunk, Ordering::Relaxed);
+                            if first >= T::MAX {
+                                break;
+                            }
+                            for index in first..(first + chunk).min(T::MAX) {
+                                match backward {
+                                    true => sweep.backward::<S, T>(index),
+                                    false => sweep.forward::<S, T>(index),
+                                }
+                            }
+                        }
+                    });
                 }
+            });
 
-                let puzzle = T::from_coord(index);
+            let highest = highest.load(Ordering::Relaxed);
 
-                // apply all moves to the current state, update the new indexes if they aren't set
-                for &mv in crate::mv::Move::ALL {
-                    let new_state = puzzle.clone() * mv;
-                    let new_index = T::to_coord(&new_state);
-                    if new_index == 0 {
-                        continue;
-                    }
+            // nothing can lower the costs of the unexpanded entries once every entry is set
+            if filled.load(Ordering::Relaxed) >= T::MAX {
+                progress!("Filled all entries in the table, stopping at depth {highest}");
+                break;
+            }
 
-                    if table[new_index] == 0 {
-                        table[new_index] = depth;
-                        total_filled += 1;
-                    }
-                }
+            // some coordinates can't reach every index
+            if cost + 1 >= highest + reach {
+                progress!("No new entries after depth {cost}, stopping");
+                break;
             }
         }
 
         let elapsed = start.elapsed();
-        eprintln!("time taken to generate lookup table: {:?}", elapsed);
+        progress!("time taken to generate lookup table: {:?}", elapsed);
 
         Self::new(table)
     }
@@ -98,10 +162,122 @@ where
     }
 }
 
+/// entries that haven't been reached yet
+const UNSET: u8 = u8::MAX;
+/// how many entries a generating thread takes at a time
+const CHUNK: usize = 1 << 14;
+
+/// one depth of table generation, shared by the generating threads
+struct Sweep<'a> {
+    entries: &'a [AtomicU8],
+    moves: &'static [Move],
+    metric: Metric,
+    /// the cost of the entries being expanded, every cheaper entry is already expanded
+    cost: u8,
+    filled: &'a AtomicUsize,
+    highest: &'a AtomicU8,
+}
+
+impl Sweep<'_> {
+    /// lowers the costs of the neighbours of `index` if it's at the current cost
+    fn forward<S, T>(&self, index: usize)
+    where
+        S: Puzzle + Clone + std::ops::Mul<Move, Output = S>,
+        T: Coordinate<S>,
+    {
+        if self.entries[index].load(Ordering::Relaxed) != self.cost {
+            return;
+        }
+
+        let mut puzzle = None;
+        for &mv in self.moves {
+            let new_index = neighbour::<S, T>(index, &mut puzzle, mv);
+            let new_cost = self.cost + mv.cost(self.metric);
+
+            let old_cost = self.entries[new_index].fetch_min(new_cost, Ordering::Relaxed);
+            if old_cost == UNSET {
+                self.filled.fetch_add(1, Ordering::Relaxed);
+            }
+            if new_cost < old_cost {
+                self.highest.fetch_max(new_cost, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// gives `index` the next cost if it has a final neighbour a move that far away. Every move
+    /// set has the inverse of each move, so a neighbour of `index` also has `index` as a neighbour
+    fn backward<S, T>(&self, index: usize)
+    where
+        S: Puzzle + Clone + std::ops::Mul<Move, Output = S>,
+        T: Coordinate<S>,
+    {
+        let next = self.cost + 1;
+        let old_cost = self.entries[index].load(Ordering::Relaxed);
+        if old_cost <= next {
+            return;
+        }
+
+        let mut puzzle = None;
+        let reached = self.moves.iter().any(|&mv| {
+            let neighbour = neighbour::<S, T>(index, &mut puzzle, mv);
+            let cost = self.entries[neighbour].load(Ordering::Relaxed);
+            cost.checked_add(mv.cost(self.metric)) == Some(next)
+        });
+
+        if reached {
+            // only this thread writes to `index`, and the other threads only look for entries at
+            // the current cost or cheaper
+            self.entries[index].store(next, Ordering::Relaxed);
+            if old_cost == UNSET {
+                self.filled.fetch_add(1, Ordering::Relaxed);
+            }
+            self.highest.fetch_max(next, Ordering::Relaxed);
+        }
+    }
+}
+
+/// the coordinate `mv` takes `index` to, through a move table when `T` has one. Otherwise the
+/// puzzle is built the first time it's needed and kept for the other moves
+fn neighbour<S, T>(index: usize, puzzle: &mut Option<S>, mv: Move) -> usize
+where
+    S: Puzzle + Clone + std::ops::Mul<Move, Output = S>,
+    T: Coordinate<S>,
+{
+    T::turn(index, mv).unwrap_or_else(|| {
+        let puzzle = puzzle.get_or_insert_with(|| T::from_coord(index));
+        T::to_coord(&(puzzle.clone() * mv).reoriented())
+    })
+}
+
+/// the moves a table for `T` is generated with, coordinates that allow every face turn use the
+/// moves of the metric
+pub(crate) fn generating_moves<S, T: Coordinate<S>>(metric: Metric) -> &'static [Move] {
+    if T::MOVES == Move::ALL {
+        metric.moves()
+    } else {
+        T::MOVES
+    }
+}
+
 pub trait Coordinate<T> {
     const MAX: usize;
+    /// the moves used to generate the pruning table, they must keep the puzzle in states the
+    /// coordinate can describe
+    const MOVES: &'static [Move] = Move::ALL;
     fn to_coord(state: &T) -> usize;
     fn from_coord(coord: usize) -> T;
+    /// changes `state` so it has the coordinate `coord`, leaving the parts of it the coordinate
+    /// doesn't describe as they are where possible
+    fn set_coord(state: &mut T, coord: usize);
+    /// a name that stays the same between builds, written into table file headers. The names
+    /// `std::any::type_name` gives can change with the compiler or when a module moves
+    fn name() -> String;
+
+    /// the coordinate `mv` takes `coord` to, from a move table if the coordinate has one with `mv`
+    /// in it. Otherwise it's worked out by turning the state `from_coord` gives
+    fn turn(_coord: usize, _mv: Move) -> Option<usize> {
+        None
+    }
 }
 
 pub struct EO;
@@ -109,6 +285,10 @@ pub struct EO;
 impl Coordinate<Cube> for EO {
     const MAX: usize = 2usize.pow(11);
 
+    fn name() -> String {
+        "EO".to_string()
+    }
+
     fn to_coord(state: &Cube) -> usize {
         state
             .eo
@@ -132,6 +312,14 @@ impl Coordinate<Cube> for EO {
         cube.eo = digits;
         cube
     }
+
+    fn set_coord(state: &mut Cube, coord: usize) {
+        state.eo = Self::from_coord(coord).eo;
+    }
+
+    fn turn(coord: usize, mv: Move) -> Option<usize> {
+        Self::move_table().get(coord, mv)
+    }
 }
 
 pub struct CornerOrientation;
@@ -140,6 +328,10 @@ pub struct CornerPermutation;
 impl Coordinate<Cube> for CornerOrientation {
     const MAX: usize = 2187;
 
+    fn name() -> String {
+        "CornerOrientation".to_string()
+    }
+
     fn to_coord(state: &Cube) -> usize {
         state
             .co
@@ -165,11 +357,23 @@ impl Coordinate<Cube> for CornerOrientation {
 
         cube
     }
+
+    fn set_coord(state: &mut Cube, coord: usize) {
+        state.co = Self::from_coord(coord).co;
+    }
+
+    fn turn(coord: usize, mv: Move) -> Option<usize> {
+        Self::move_table().get(coord, mv)
+    }
 }
 
 impl Coordinate<Cube> for CornerPermutation {
     const MAX: usize = 40320;
 
+    fn name() -> String {
+        "CornerPermutation".to_string()
+    }
+
     fn to_coord(state: &Cube) -> usize {
         let mut x = 0;
         for i in (1..8).rev() {
@@ -214,79 +418,101 @@ impl Coordinate<Cube> for CornerPermutation {
         cube.cp = cp;
         cube
     }
+
+    fn set_coord(state: &mut Cube, coord: usize) {
+        state.cp = Self::from_coord(coord).cp;
+    }
+
+    fn turn(coord: usize, mv: Move) -> Option<usize> {
+        Self::move_table().get(coord, mv)
+    }
 }
 
-/*
+/// both coordinates, indexed `c0 * C1::MAX + c1`. The state is built by writing each component
+/// into the first one's, so the components should describe different parts of the puzzle
 impl<S, C0, C1> Coordinate<S> for (C0, C1)
 where
     C0: Coordinate<S>,
     C1: Coordinate<S>,
 {
     const MAX: usize = C0::MAX * C1::MAX;
+    const MOVES: &'static [Move] = fewest_moves(&[C0::MOVES, C1::MOVES]);
+
+    fn name() -> String {
+        format!("({}, {})", C0::name(), C1::name())
+    }
 
     fn to_coord(state: &S) -> usize {
         C0::to_coord(state) * C1::MAX + C1::to_coord(state)
     }
 
     fn from_coord(coord: usize) -> S {
-        let c0 = coord / C1::MAX;
-        let c1 = coord % C1::MAX;
-        (C0::from_coord(c0), C1::from_coord(c1))
+        let mut state = C0::from_coord(coord / C1::MAX);
+        C1::set_coord(&mut state, coord % C1::MAX);
+        state
     }
-}
-*/
-impl Coordinate<Cube> for (CornerOrientation, CornerPermutation) {
-    const MAX: usize = CornerOrientation::MAX * CornerPermutation::MAX;
 
-    fn to_coord(state: &Cube) -> usize {
-        CornerOrientation::to_coord(state) * CornerPermutation::MAX
-            + CornerPermutation::to_coord(state)
+    fn set_coord(state: &mut S, coord: usize) {
+        C0::set_coord(state, coord / C1::MAX);
+        C1::set_coord(state, coord % C1::MAX);
     }
 
-    fn from_coord(coord: usize) -> Cube {
-        let c0 = coord / CornerPermutation::MAX;
-        let c1 = coord % CornerPermutation::MAX;
-        let (mut co, cp) = (
-            CornerOrientation::from_coord(c0),
-            CornerPermutation::from_coord(c1),
-        );
-
-        co.cp = cp.cp;
-        co
+    fn turn(coord: usize, mv: Move) -> Option<usize> {
+        Some(C0::turn(coord / C1::MAX, mv)? * C1::MAX + C1::turn(coord % C1::MAX, mv)?)
     }
 }
 
-impl<const LOW: usize, const HIGH: usize> Coordinate<Cube>
-    for (
-        PartialEdgeOrientation<LOW, HIGH>,
-        PartialEdgePermutation<LOW, HIGH>,
-    )
+impl<S, C0, C1, C2> Coordinate<S> for (C0, C1, C2)
 where
-    PartialEdgeOrientation<LOW, HIGH>: Coordinate<Cube>,
-    PartialEdgePermutation<LOW, HIGH>: Coordinate<Cube>,
+    C0: Coordinate<S>,
+    C1: Coordinate<S>,
+    C2: Coordinate<S>,
 {
-    const MAX: usize =
-        PartialEdgeOrientation::<LOW, HIGH>::MAX * PartialEdgePermutation::<LOW, HIGH>::MAX;
+    const MAX: usize = C0::MAX * C1::MAX * C2::MAX;
+    const MOVES: &'static [Move] = fewest_moves(&[C0::MOVES, C1::MOVES, C2::MOVES]);
 
-    fn to_coord(state: &Cube) -> usize {
-        PartialEdgeOrientation::<LOW, HIGH>::to_coord(state)
-            * PartialEdgePermutation::<LOW, HIGH>::MAX
-            + PartialEdgePermutation::<LOW, HIGH>::to_coord(state)
+    fn name() -> String {
+        format!("({}, {}, {})", C0::name(), C1::name(), C2::name())
     }
 
-    fn from_coord(coord: usize) -> Cube {
-        let c0 = coord / PartialEdgePermutation::<LOW, HIGH>::MAX;
-        let c1 = coord % PartialEdgePermutation::<LOW, HIGH>::MAX;
-        let (mut eo, ep) = (
-            PartialEdgeOrientation::<LOW, HIGH>::from_coord(c0),
-            PartialEdgePermutation::<LOW, HIGH>::from_coord(c1),
-        );
+    fn to_coord(state: &S) -> usize {
+        (C0::to_coord(state) * C1::MAX + C1::to_coord(state)) * C2::MAX + C2::to_coord(state)
+    }
+
+    fn from_coord(coord: usize) -> S {
+        let mut state = C0::from_coord(coord / (C1::MAX * C2::MAX));
+        C1::set_coord(&mut state, coord / C2::MAX % C1::MAX);
+        C2::set_coord(&mut state, coord % C2::MAX);
+        state
+    }
+
+    fn set_coord(state: &mut S, coord: usize) {
+        C0::set_coord(state, coord / (C1::MAX * C2::MAX));
+        C1::set_coord(state, coord / C2::MAX % C1::MAX);
+        C2::set_coord(state, coord % C2::MAX);
+    }
 
-        eo.ep = ep.ep;
-        eo
+    fn turn(coord: usize, mv: Move) -> Option<usize> {
+        let c0 = C0::turn(coord / (C1::MAX * C2::MAX), mv)?;
+        let c1 = C1::turn(coord / C2::MAX % C1::MAX, mv)?;
+        Some((c0 * C1::MAX + c1) * C2::MAX + C2::turn(coord % C2::MAX, mv)?)
     }
 }
 
+/// the smallest of some move sets. The ones coordinates use are nested, so it's the moves that
+/// keep every coordinate in states it can describe
+const fn fewest_moves(sets: &[&'static [Move]]) -> &'static [Move] {
+    let mut fewest = sets[0];
+    let mut i = 1;
+    while i < sets.len() {
+        if sets[i].len() < fewest.len() {
+            fewest = sets[i];
+        }
+        i += 1;
+    }
+    fewest
+}
+
 pub struct PartialEdges<const LOW: usize, const HIGH: usize>;
 
 impl<const LOW: usize, const HIGH: usize> PartialEdges<LOW, HIGH> {
@@ -304,6 +530,10 @@ where
 {
     const MAX: usize = Self::PERMUTATION_SIZE * Self::ORIENTATION_SIZE;
 
+    fn name() -> String {
+        format!("PartialEdges<{LOW}, {HIGH}>")
+    }
+
     fn to_coord(state: &Cube) -> usize {
         debug_assert!(LOW < HIGH, "LOW must be < HIGH");
         debug_assert!(HIGH <= 12, "HIGH must be <= 12");
@@ -413,6 +643,24 @@ where
         cube.eo = eo;
         cube
     }
+
+    fn set_coord(state: &mut Cube, coord: usize) {
+        // the other edges fill the positions left over in the order they were in, orientations
+        // belong to the positions so theirs stay where they are
+        let edges = Self::from_coord(coord);
+        let is_tracked = |edge: u8| (LOW..HIGH).contains(&(edge as usize));
+        let ep = state.ep;
+        let mut others = ep.into_iter().filter(|&edge| !is_tracked(edge));
+
+        for (i, edge) in edges.ep.into_iter().enumerate() {
+            if is_tracked(edge) {
+                state.ep[i] = edge;
+                state.eo[i] = edges.eo[i];
+            } else {
+                state.ep[i] = others.next().unwrap();
+            }
+        }
+    }
 }
 
 pub struct PartialEdgeOrientation<const LOW: usize, const HIGH: usize>;
@@ -420,6 +668,10 @@ pub struct PartialEdgeOrientation<const LOW: usize, const HIGH: usize>;
 impl<const LOW: usize, const HIGH: usize> Coordinate<Cube> for PartialEdgeOrientation<LOW, HIGH> {
     const MAX: usize = 2usize.pow((HIGH - LOW) as u32);
 
+    fn name() -> String {
+        format!("PartialEdgeOrientation<{LOW}, {HIGH}>")
+    }
+
     fn to_coord(state: &Cube) -> usize {
         debug_assert!(LOW < HIGH, "LOW must be < HIGH");
         debug_assert!(HIGH <= 12, "HIGH must be <= 12");
@@ -446,9 +698,13 @@ impl<const LOW: usize, const HIGH: usize> Coordinate<Cube> for PartialEdgeOrient
 
         cube
     }
+
+    fn set_coord(state: &mut Cube, coord: usize) {
+        state.eo[LOW..HIGH].copy_from_slice(&Self::from_coord(coord).eo[LOW..HIGH]);
+    }
 }
 
-const fn factorial(mut n: usize) -> usize {
+pub(crate) const fn factorial(mut n: usize) -> usize {
     let mut total = 1;
     while n > 0 {
         total *= n;
@@ -458,7 +714,7 @@ const fn factorial(mut n: usize) -> usize {
 }
 
 /// number of ways to choose k things from a set of n
-const fn choose(n: usize, k: usize) -> usize {
+pub(crate) const fn choose(n: usize, k: usize) -> usize {
     if k > n {
         0
     } else {
@@ -467,7 +723,7 @@ const fn choose(n: usize, k: usize) -> usize {
     }
 }
 
-fn greatest_combination(n: usize, k: usize) -> (u8, usize) {
+pub(crate) fn greatest_combination(n: usize, k: usize) -> (u8, usize) {
     let mut prev = 0;
     let mut x = 0;
 
@@ -493,6 +749,10 @@ where
         arrangements * choices
     };
 
+    fn name() -> String {
+        format!("PartialEdgePermutation<{LOW}, {HIGH}>")
+    }
+
     fn to_coord(state: &Cube) -> usize {
         debug_assert!(LOW < HIGH, "LOW ({LOW}) should be < HIGH ({HIGH})");
         debug_assert!(HIGH <= 12, "HIGH ({HIGH}) should be <= 12");
@@ -562,14 +822,219 @@ where
             let epi = remaining.remove(i - digit as usize);
             cube.ep[LOW + i] = epi;
         }
+        Self::fill_others(&mut cube.ep, std::array::from_fn(|i| i as u8), false);
         cube
     }
+
+    fn set_coord(state: &mut Cube, coord: usize) {
+        let ep = state.ep;
+        state.ep[LOW..HIGH].copy_from_slice(&Self::from_coord(coord).ep[LOW..HIGH]);
+        Self::fill_others(&mut state.ep, ep, parity(&ep));
+    }
+}
+
+impl<const LOW: usize, const HIGH: usize> PartialEdgePermutation<LOW, HIGH> {
+    /// puts the edges that aren't in positions LOW..HIGH in the other positions, in the order
+    /// they're in `order`. The last two are swapped if that's needed for the permutation to be
+    /// `odd`, so the corners' parity still matches
+    fn fill_others(ep: &mut [u8; 12], order: [u8; 12], odd: bool) {
+        let placed = ep[LOW..HIGH].to_vec();
+        let mut others = order.into_iter().filter(|edge| !placed.contains(edge));
+        let positions: Vec<usize> = (0..LOW).chain(HIGH..12).collect();
+        for &i in &positions {
+            ep[i] = others.next().unwrap();
+        }
+
+        if parity(ep) != odd
+            && let [.., a, b] = positions[..]
+        {
+            ep.swap(a, b);
+        }
+    }
+}
+
+/// index of a permutation of 0..N, the identity is 0
+pub(crate) fn permutation_to_coord<const N: usize>(perm: &[u8; N]) -> usize {
+    let mut x = 0;
+    for i in (1..N).rev() {
+        let s = perm[..i].iter().filter(|&&p| p > perm[i]).count();
+        x = (x + s) * i;
+    }
+    x
+}
+
+pub(crate) fn permutation_from_coord<const N: usize>(coord: usize) -> [u8; N] {
+    debug_assert!(coord < factorial(N), "number {coord} out of bounds");
+
+    let mut lehmer_code = [0; N];
+    let mut n = coord;
+    for i in (1..N).rev() {
+        lehmer_code[i] = n / factorial(i);
+        n %= factorial(i);
+    }
+
+    let mut remaining = Vec::from_iter(0..N as u8);
+    let mut perm = [0; N];
+    for i in (0..N).rev() {
+        perm[i] = remaining.remove(i - lehmer_code[i]);
+    }
+    perm
+}
+
+/// the positions of the 4 E slice edges (4..8), ignoring their order and orientation
+pub struct UDSlice;
+
+impl Coordinate<Cube> for UDSlice {
+    const MAX: usize = choose(12, 4);
+
+    fn name() -> String {
+        "UDSlice".to_string()
+    }
+
+    fn to_coord(state: &Cube) -> usize {
+        // positions are shifted so the E slice comes first, which makes the solved state 0
+        let mut positions = state
+            .ep
+            .iter()
+            .enumerate()
+            .filter(|&(_, &edge)| (4..8).contains(&edge))
+            .map(|(i, _)| (i + 8) % 12);
+
+        let mut sorted = [0; 4];
+        sorted.fill_with(|| positions.next().unwrap());
+        sorted.sort();
+
+        sorted
+            .iter()
+            .enumerate()
+            .map(|(k, &c)| choose(c, k + 1))
+            .sum()
+    }
+
+    fn from_coord(coord: usize) -> Cube {
+        debug_assert!(coord < Self::MAX, "number {coord} out of bounds");
+
+        let mut ep = [None; 12];
+        let mut choice = coord;
+        for k in (1..=4).rev() {
+            let (i, x) = greatest_combination(choice, k);
+            ep[(i as usize + 4) % 12] = Some(k as u8 + 3);
+            choice -= x;
+        }
+
+        let mut remaining = [0, 1, 2, 3, 8, 9, 10, 11].into_iter();
+
+        let mut cube = Cube::solved();
+        cube.ep = ep.map(|x| x.unwrap_or_else(|| remaining.next().unwrap()));
+        cube
+    }
+
+    fn set_coord(state: &mut Cube, coord: usize) {
+        // the slice edges and the others each keep their order, only which positions the slice
+        // edges are in changes
+        let is_slice = |edge: u8| (4..8).contains(&edge);
+        let ep = state.ep;
+        let mut slice = ep.into_iter().filter(|&edge| is_slice(edge));
+        let mut others = ep.into_iter().filter(|&edge| !is_slice(edge));
+
+        state.ep = Self::from_coord(coord).ep.map(|edge| match is_slice(edge) {
+            true => slice.next().unwrap(),
+            false => others.next().unwrap(),
+        });
+    }
+
+    fn turn(coord: usize, mv: Move) -> Option<usize> {
+        Self::move_table().get(coord, mv)
+    }
+}
+
+/// the order of the E slice edges, only defined when they're in the E slice
+pub struct SlicePermutation;
+
+impl Coordinate<Cube> for SlicePermutation {
+    const MAX: usize = 24;
+    const MOVES: &'static [Move] = Move::DOMINO;
+
+    fn name() -> String {
+        "SlicePermutation".to_string()
+    }
+
+    fn to_coord(state: &Cube) -> usize {
+        permutation_to_coord(&std::array::from_fn::<_, 4, _>(|i| state.ep[i + 4] - 4))
+    }
+
+    fn from_coord(coord: usize) -> Cube {
+        let perm = permutation_from_coord::<4>(coord);
+
+        let mut cube = Cube::solved();
+        for (i, edge) in perm.into_iter().enumerate() {
+            cube.ep[i + 4] = edge + 4;
+        }
+        cube
+    }
+
+    fn set_coord(state: &mut Cube, coord: usize) {
+        state.ep[4..8].copy_from_slice(&Self::from_coord(coord).ep[4..8]);
+    }
+
+    fn turn(coord: usize, mv: Move) -> Option<usize> {
+        Self::move_table().get(coord, mv)
+    }
+}
+
+/// the order of the U and D layer edges, only defined when the E slice edges are in the E slice
+pub struct UDEdgePermutation;
+
+impl UDEdgePermutation {
+    const POSITIONS: [usize; 8] = [0, 1, 2, 3, 8, 9, 10, 11];
+}
+
+impl Coordinate<Cube> for UDEdgePermutation {
+    const MAX: usize = 40320;
+    const MOVES: &'static [Move] = Move::DOMINO;
+
+    fn name() -> String {
+        "UDEdgePermutation".to_string()
+    }
+
+    fn to_coord(state: &Cube) -> usize {
+        // edges 8..12 are renumbered to 4..8 so the U and D edges are a permutation of 0..8
+        let perm = Self::POSITIONS.map(|i| match state.ep[i] {
+            edge @ 0..4 => edge,
+            edge => edge - 4,
+        });
+        permutation_to_coord(&perm)
+    }
+
+    fn from_coord(coord: usize) -> Cube {
+        let perm = permutation_from_coord::<8>(coord);
+
+        let mut cube = Cube::solved();
+        for (i, edge) in zip(Self::POSITIONS, perm) {
+            cube.ep[i] = match edge {
+                0..4 => edge,
+                _ => edge + 4,
+            };
+        }
+        cube
+    }
+
+    fn set_coord(state: &mut Cube, coord: usize) {
+        let cube = Self::from_coord(coord);
+        for i in Self::POSITIONS {
+            state.ep[i] = cube.ep[i];
+        }
+    }
+
+    fn turn(coord: usize, mv: Move) -> Option<usize> {
+        Self::move_table().get(coord, mv)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::cube::Cube;
+    use crate::cube::{Cube, SOLVED};
     use crate::mv::Move::*;
 
     #[test]
@@ -597,6 +1062,58 @@ mod tests {
         );
     }
 
+    /// the costs found by Dijkstra's algorithm one entry at a time
+    fn reference<T: Coordinate<Cube>>(metric: Metric) -> Vec<u8> {
+        let mut costs = vec![UNSET; T::MAX];
+        let mut queue = std::collections::BinaryHeap::new();
+        queue.push(std::cmp::Reverse((0, T::to_coord(&SOLVED))));
+
+        while let Some(std::cmp::Reverse((cost, index))) = queue.pop() {
+            if costs[index] <= cost {
+                continue;
+            }
+            costs[index] = cost;
+            for &mv in generating_moves::<Cube, T>(metric) {
+                let next = T::to_coord(&(T::from_coord(index) * mv).reoriented());
+                queue.push(std::cmp::Reverse((cost + mv.cost(metric), next)));
+            }
+        }
+        costs
+    }
+
+    #[test]
+    fn threaded_generation() {
+        // small chunks so the threads share every depth, in both directions
+        for metric in [Metric::Htm, Metric::Qtm, Metric::Stm] {
+            let expected = reference::<CornerOrientation>(metric);
+            for threads in [1, 3] {
+                let table =
+                    PruningTable::<Cube, CornerOrientation>::generate_chunked(metric, threads, 64);
+                assert_eq!(table.0.as_slice(), expected.as_slice(), "{metric:?}");
+            }
+        }
+
+        // and a table many chunks long, which only fills up at the last few depths
+        let expected = reference::<CornerPermutation>(Metric::Htm);
+        let table = PruningTable::<Cube, CornerPermutation>::generate_chunked(Metric::Htm, 3, 1024);
+        assert_eq!(table.0.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn weighted_generation() {
+        let htm = PruningTable::<Cube, EO>::generate();
+        let qtm = PruningTable::<Cube, EO>::generate_for(Metric::Qtm);
+
+        assert_eq!(qtm.lower_bound(&(SOLVED * F)), 1);
+        assert_eq!(qtm.lower_bound(&(SOLVED * F2)), 0);
+        assert_eq!(htm.lower_bound(&(SOLVED * F * B)), 2);
+        assert_eq!(qtm.lower_bound(&(SOLVED * F * B)), 2);
+        // a quarter turn solution is at most twice as long as a half turn one
+        for (&htm, &qtm) in zip(htm.0.iter(), qtm.0.iter()) {
+            assert!(htm <= qtm && qtm <= 2 * htm);
+        }
+    }
+
     #[test]
     fn corner_permutation_round_trip() {
         let scramble = R * U * U * F * L * B;
@@ -679,6 +1196,74 @@ mod tests {
         }
     }
 
+    #[test]
+    fn partial_edge_permutation_states_are_valid() {
+        type Coord = PartialEdgePermutation<0, 6>;
+        let scramble = R * U * U * F * L * B;
+
+        for coord in [0, 1, 12345, Coord::MAX - 1] {
+            let cube = Coord::from_coord(coord);
+            assert_eq!(cube.validate(), Ok(()));
+            assert_eq!(Coord::to_coord(&cube), coord);
+
+            let mut cube = scramble.clone();
+            Coord::set_coord(&mut cube, coord);
+            assert_eq!(cube.validate(), Ok(()));
+            assert_eq!(Coord::to_coord(&cube), coord);
+            assert_eq!(cube.cp, scramble.cp);
+        }
+
+        type Tuple = (Coord, CornerOrientation);
+        let cube = Tuple::from_coord(Tuple::MAX - 1);
+        assert_eq!(cube.validate(), Ok(()));
+    }
+
+    #[test]
+    fn permutation_coordinates() {
+        let scramble = R * U * U * F * L * B;
+
+        assert_eq!(
+            permutation_to_coord(&scramble.cp),
+            CornerPermutation::to_coord(&scramble)
+        );
+        for coord in [0, 1, 4467, 40319] {
+            assert_eq!(
+                permutation_from_coord::<8>(coord),
+                CornerPermutation::from_coord(coord).cp
+            );
+        }
+    }
+
+    #[test]
+    fn ud_slice_round_trip() {
+        assert_eq!(UDSlice::to_coord(&Cube::solved()), 0);
+
+        for coord in 0..UDSlice::MAX {
+            assert_eq!(UDSlice::to_coord(&UDSlice::from_coord(coord)), coord);
+        }
+
+        let scramble = R * U * U * F * L * B;
+        let reconstructed = UDSlice::from_coord(UDSlice::to_coord(&scramble));
+        for (a, b) in zip(reconstructed.ep, scramble.ep) {
+            assert_eq!((4..8).contains(&a), (4..8).contains(&b));
+        }
+    }
+
+    #[test]
+    fn domino_coordinates_round_trip() {
+        let scramble = U * R2 * D3 * F2 * U2 * B2 * L2 * D;
+
+        for coord in [SlicePermutation::to_coord(&scramble), 0, 23] {
+            let state = SlicePermutation::from_coord(coord);
+            assert_eq!(SlicePermutation::to_coord(&state), coord);
+        }
+
+        let coord = <(UDEdgePermutation, SlicePermutation)>::to_coord(&scramble);
+        let state = <(UDEdgePermutation, SlicePermutation)>::from_coord(coord);
+        assert_eq!(state.ep, scramble.ep);
+        assert_eq!(UDEdgePermutation::to_coord(&Cube::solved()), 0);
+    }
+
     #[test]
     fn partial_edges_round_trip() {
         {
@@ -692,4 +1277,47 @@ mod tests {
             assert_eq!(COORDINATE, reconstructed_coordinate);
         }
     }
+
+    #[test]
+    fn set_coord_keeps_other_parts() {
+        let scramble = R * U * U * F * L * B * D3 * R2;
+
+        let mut state = scramble.clone();
+        UDSlice::set_coord(&mut state, 0);
+        assert_eq!(UDSlice::to_coord(&state), 0);
+        assert_eq!(EO::to_coord(&state), EO::to_coord(&scramble));
+        assert_eq!((state.cp, state.co), (scramble.cp, scramble.co));
+
+        let mut state = scramble.clone();
+        PartialEdges::<0, 4>::set_coord(&mut state, 1234);
+        assert_eq!(PartialEdges::<0, 4>::to_coord(&state), 1234);
+        let mut sorted = state.ep;
+        sorted.sort();
+        assert_eq!(sorted, SOLVED.ep);
+    }
+
+    #[test]
+    fn tuple_coordinates() {
+        type Triple = (CornerOrientation, EO, UDSlice);
+        let scramble = R * U * U * F * L * B * D3 * R2;
+
+        assert_eq!(Triple::MAX, 2187 * 2048 * 495);
+        assert_eq!(Triple::to_coord(&SOLVED), 0);
+        let coord = Triple::to_coord(&scramble);
+        assert_eq!(
+            coord,
+            (CornerOrientation::to_coord(&scramble) * EO::MAX + EO::to_coord(&scramble))
+                * UDSlice::MAX
+                + UDSlice::to_coord(&scramble)
+        );
+        assert_eq!(Triple::to_coord(&Triple::from_coord(coord)), coord);
+
+        let mut state = SOLVED.clone();
+        Triple::set_coord(&mut state, coord);
+        assert_eq!(Triple::to_coord(&state), coord);
+
+        assert_eq!(<(CornerOrientation, CornerPermutation)>::MOVES, Move::ALL);
+        assert_eq!(<(EO, SlicePermutation)>::MOVES, Move::DOMINO);
+        assert_eq!(<(SlicePermutation, EO, UDSlice)>::MOVES, Move::DOMINO);
+    }
 }
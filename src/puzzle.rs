@@ -1,4 +1,13 @@
 pub trait Puzzle {
     fn is_solved(&self) -> bool;
     fn solved() -> Self;
+
+    /// the state relative to the way the puzzle is held after a move that turned it as a whole,
+    /// like a slice turn moving the centers. Puzzles that can't be turned as a whole stay the same
+    fn reoriented(self) -> Self
+    where
+        Self: Sized,
+    {
+        self
+    }
 }
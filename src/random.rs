@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use crate::algorithm::Algorithm;
+use crate::cube::{Cube, SOLVED, parity};
+use crate::heuristics::Heuristic;
+use crate::two_phase::{Phase1Cube, Phase2Cube, two_phase};
+
+/// SplitMix64, small and fast with a seed that reproduces the same states everywhere
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    /// seeded from the clock, along with the seed so the same states can be made again
+    pub fn from_clock() -> (Self, u64) {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        (Rng::new(seed), seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// uniform in `0..n`, values from the partial range at the top are thrown away so none are
+    /// more likely than the others
+    pub fn below(&mut self, n: u64) -> u64 {
+        let zone = u64::MAX - u64::MAX % n;
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return x % n;
+            }
+        }
+    }
+
+    /// Fisher-Yates
+    fn shuffle(&mut self, pieces: &mut [u8]) {
+        for i in (1..pieces.len()).rev() {
+            pieces.swap(i, self.below(i as u64 + 1) as usize);
+        }
+    }
+}
+
+impl Cube {
+    /// every reachable state with solved centers equally likely. The pieces are shuffled and the
+    /// last two corners swapped when the permutations' parities differ, which pairs each
+    /// unreachable arrangement with one reachable one. The last edge and corner are oriented to
+    /// make the flips and twists add up
+    pub fn random(rng: &mut Rng) -> Cube {
+        let mut cube = SOLVED;
+
+        rng.shuffle(&mut cube.ep);
+        rng.shuffle(&mut cube.cp);
+        if parity(&cube.ep) != parity(&cube.cp) {
+            cube.cp.swap(6, 7);
+        }
+
+        for i in 0..11 {
+            cube.eo[i] = rng.below(2) as u8;
+        }
+        cube.eo[11] = cube.eo[..11].iter().sum::<u8>() % 2;
+        for i in 0..7 {
+            cube.co[i] = rng.below(3) as u8;
+        }
+        cube.co[7] = (3 - cube.co[..7].iter().sum::<u8>() % 3) % 3;
+
+        cube
+    }
+}
+
+/// a scramble that leaves `cube`, the inverse of a Kociemba solution of it. The first solution of
+/// at most `target` moves is used, or the shortest found once `time_limit` runs out
+pub fn scramble_for(
+    cube: &Cube,
+    target: u8,
+    time_limit: Duration,
+    phase1: impl Heuristic<Phase1Cube>,
+    phase2: impl Heuristic<Phase2Cube>,
+) -> Option<Algorithm> {
+    let solution = two_phase(cube, target, time_limit, phase1, phase2)?;
+    Some(Algorithm::from(solution).inverse())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heuristics::ZeroBound;
+
+    #[test]
+    fn random_cubes() {
+        let mut rng = Rng::new(7);
+        let mut odd = 0;
+        let mut twists = [[0; 3]; 8];
+        for _ in 0..3000 {
+            let cube = Cube::random(&mut rng);
+            assert_eq!(cube.validate(), Ok(()));
+            odd += parity(&cube.cp) as usize;
+            for (i, &co) in cube.co.iter().enumerate() {
+                twists[i][co as usize] += 1;
+            }
+        }
+
+        // about half of the states have odd permutations, and every twist is as likely anywhere
+        assert!((1300..1700).contains(&odd), "{odd}");
+        assert!(twists.iter().flatten().all(|&n| (850..1150).contains(&n)));
+
+        let states = |seed| {
+            let mut rng = Rng::new(seed);
+            (0..5).map(|_| Cube::random(&mut rng)).collect::<Vec<_>>()
+        };
+        assert_eq!(states(1), states(1));
+        assert_ne!(states(1), states(2));
+    }
+
+    #[test]
+    fn below_is_in_range() {
+        let mut rng = Rng::new(0);
+        let mut seen = [false; 5];
+        for _ in 0..100 {
+            seen[rng.below(5) as usize] = true;
+        }
+        assert!(seen.iter().all(|&seen| seen));
+    }
+
+    #[test]
+    fn scrambles_reach_the_state() {
+        let cube = "R U F".parse::<Algorithm>().unwrap().to_cube();
+        let scramble =
+            scramble_for(&cube, 3, Duration::from_secs(10), ZeroBound, ZeroBound).unwrap();
+        assert_eq!(scramble.to_cube(), cube);
+    }
+}
@@ -0,0 +1,313 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::mv::Move;
+
+/// how many nodes are searched between looking at the clock and the cancellation token
+pub(crate) const CHECK_INTERVAL: u64 = 1 << 12;
+
+/// stops the searches it's been given to, from any thread. Clones share the same flag
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        CancelToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// the first moves of the path being searched that a heartbeat carries
+const PREFIX_LENGTH: usize = 4;
+
+/// what an optimal search is doing, for showing live progress
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchEvent {
+    DepthStarted {
+        depth: u8,
+    },
+    /// every path of this cost has been searched without finding a solution
+    DepthFinished {
+        depth: u8,
+        /// the nodes searched at this depth only
+        nodes: u64,
+        elapsed: Duration,
+        /// the mean number of children of the nodes that weren't leaves. The next depth takes
+        /// about this many times as long
+        branching_factor: f64,
+    },
+    SolutionFound {
+        solution: Vec<Move>,
+        /// the nodes searched at every depth
+        nodes: u64,
+        elapsed: Duration,
+    },
+    /// sent every so often while a depth is being searched
+    Heartbeat {
+        /// the nodes searched at every depth so far
+        nodes: u64,
+        elapsed: Duration,
+        /// the first few moves of the path being searched. Moves are tried in the order of
+        /// `Metric::moves`, so this shows how far through the depth the search is
+        current_path_prefix: Vec<Move>,
+    },
+}
+
+/// receives the events of a search, on whichever of its threads they happen. To get them
+/// somewhere else, send them down a channel from the callback
+#[derive(Clone)]
+pub struct Observer {
+    callback: Arc<dyn Fn(&SearchEvent) + Send + Sync>,
+    heartbeat: Duration,
+}
+
+impl Observer {
+    /// heartbeats are sent every second
+    pub fn new(callback: impl Fn(&SearchEvent) + Send + Sync + 'static) -> Self {
+        Observer {
+            callback: Arc::new(callback),
+            heartbeat: Duration::from_secs(1),
+        }
+    }
+
+    pub fn heartbeat(mut self, interval: Duration) -> Self {
+        self.heartbeat = interval;
+        self
+    }
+
+    pub(crate) fn send(&self, event: SearchEvent) {
+        (self.callback)(&event)
+    }
+}
+
+impl std::fmt::Debug for Observer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Observer")
+            .field("heartbeat", &self.heartbeat)
+            .finish_non_exhaustive()
+    }
+}
+
+/// when the next heartbeat of a search is due. Each search has its own, shared by its threads, so
+/// searches with clones of one observer don't hold back each other's heartbeats
+#[derive(Debug)]
+pub struct Heartbeats {
+    /// when the search started
+    start: Instant,
+    next: Mutex<Instant>,
+}
+
+impl Heartbeats {
+    /// the first heartbeat is a full interval after the search starts, which is now
+    pub(crate) fn new(limits: &SearchLimits) -> Self {
+        let start = Instant::now();
+        let interval = limits
+            .observer
+            .as_ref()
+            .map_or(Duration::ZERO, |o| o.heartbeat);
+        Heartbeats {
+            start,
+            next: Mutex::new(start + interval),
+        }
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// sends a heartbeat to the observer of `limits` if one is due, called every `CHECK_INTERVAL`
+    /// nodes
+    pub(crate) fn tick(&self, limits: &SearchLimits, nodes: u64, path: &[Move]) {
+        let Some(observer) = &limits.observer else {
+            return;
+        };
+        let now = Instant::now();
+        // another thread is sending one
+        let Ok(mut next) = self.next.try_lock() else {
+            return;
+        };
+        if now < *next {
+            return;
+        }
+        *next = now + observer.heartbeat;
+        drop(next);
+
+        observer.send(SearchEvent::Heartbeat {
+            nodes,
+            elapsed: now - self.start,
+            current_path_prefix: path[..path.len().min(PREFIX_LENGTH)].to_vec(),
+        });
+    }
+}
+
+/// when a search gives up before it has searched every depth, and who's told how it's going. The
+/// clock and the token are looked at every few thousand nodes, and a parallel search counts its
+/// nodes as often, so it can go a little past `max_nodes`
+#[derive(Debug, Clone, Default)]
+pub struct SearchLimits {
+    pub deadline: Option<Instant>,
+    pub max_nodes: Option<u64>,
+    pub cancel: Option<CancelToken>,
+    pub observer: Option<Observer>,
+}
+
+impl SearchLimits {
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// a deadline this long from now
+    pub fn time_limit(self, time_limit: Duration) -> Self {
+        self.deadline(Instant::now() + time_limit)
+    }
+
+    pub fn max_nodes(mut self, max_nodes: u64) -> Self {
+        self.max_nodes = Some(max_nodes);
+        self
+    }
+
+    pub fn cancel(mut self, token: CancelToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    pub fn observer(mut self, observer: Observer) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    pub(crate) fn send(&self, event: impl FnOnce() -> SearchEvent) {
+        if let Some(observer) = &self.observer {
+            observer.send(event());
+        }
+    }
+
+    /// why a search that has searched `nodes` nodes has to stop, if it does
+    pub(crate) fn check(&self, nodes: u64) -> Option<AbortReason> {
+        if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+            Some(AbortReason::Cancelled)
+        } else if self.max_nodes.is_some_and(|max_nodes| nodes >= max_nodes) {
+            Some(AbortReason::Nodes)
+        } else if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            Some(AbortReason::Deadline)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbortReason {
+    Deadline,
+    Nodes,
+    Cancelled,
+}
+
+impl std::fmt::Display for AbortReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AbortReason::Deadline => write!(f, "reached the time limit"),
+            AbortReason::Nodes => write!(f, "reached the node limit"),
+            AbortReason::Cancelled => write!(f, "was cancelled"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchOutcome {
+    Solved(Vec<Move>),
+    /// every depth up to the maximum was searched
+    NoSolution,
+    /// a limit stopped the search first
+    Aborted(AbortReason),
+}
+
+/// how a search ended, and how much searching it took
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub outcome: SearchOutcome,
+    pub nodes: u64,
+    /// every solution costs at least this much: one more than the last depth that was searched
+    /// completely, or the heuristic's bound when none was. The cost of the solution once solved
+    pub lower_bound: u8,
+}
+
+impl SearchResult {
+    pub fn solution(&self) -> Option<&[Move]> {
+        match &self.outcome {
+            SearchOutcome::Solved(path) => Some(path),
+            _ => None,
+        }
+    }
+
+    pub fn into_solution(self) -> Option<Vec<Move>> {
+        match self.outcome {
+            SearchOutcome::Solved(path) => Some(path),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits() {
+        let token = CancelToken::new();
+        let limits = SearchLimits::default().max_nodes(100).cancel(token.clone());
+        assert_eq!(limits.check(99), None);
+        assert_eq!(limits.check(100), Some(AbortReason::Nodes));
+
+        token.clone().cancel();
+        assert_eq!(limits.check(0), Some(AbortReason::Cancelled));
+
+        let limits = SearchLimits::default().deadline(Instant::now());
+        assert_eq!(limits.check(0), Some(AbortReason::Deadline));
+        assert_eq!(SearchLimits::default().check(u64::MAX), None);
+    }
+
+    #[test]
+    fn heartbeats() {
+        use crate::mv::Move::*;
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let observer = Observer::new(move |event| sink.lock().unwrap().push(event.clone()))
+            .heartbeat(Duration::from_secs(3600));
+
+        // not until an interval has passed since the search started
+        let limits = SearchLimits::default().observer(observer.clone());
+        let hourly = Heartbeats::new(&limits);
+        hourly.tick(&limits, 1, &[]);
+        assert!(events.lock().unwrap().is_empty());
+
+        // a search with a clone of the observer keeps its own time
+        let limits = limits.observer(observer.heartbeat(Duration::ZERO));
+        let heartbeats = Heartbeats::new(&limits);
+        heartbeats.tick(&limits, 2, &[R, U, F, D, L, B]);
+        hourly.tick(&limits, 3, &[]);
+        match &events.lock().unwrap()[..] {
+            [
+                SearchEvent::Heartbeat {
+                    nodes: 2,
+                    current_path_prefix,
+                    ..
+                },
+            ] => assert_eq!(current_path_prefix, &[R, U, F, D]),
+            events => panic!("{events:?}"),
+        }
+    }
+}
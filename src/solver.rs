@@ -0,0 +1,417 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::algorithm::Algorithm;
+use crate::batch::{BatchOptions, BatchResult, solve_batch};
+use crate::cube::{Cube, CubeError};
+use crate::heuristics::{DynHeuristic, HeuristicSet, MAX_HEURISTICS};
+use crate::mv::Metric;
+use crate::packed_table::Encoding;
+use crate::parallel::par_ida;
+use crate::progress::{Progress, with_progress};
+use crate::search::{AbortReason, CancelToken, Observer, SearchLimits, SearchOutcome};
+use crate::tables::{DEFAULT_TABLES, TableOptions, load_heuristics};
+
+/// every state can be solved in 20 face turns, or 26 quarter turns
+pub const DEFAULT_MAX_DEPTH: u8 = 26;
+
+/// the settings of a `Solver`, whose tables are loaded, or generated and written, by `build`
+pub struct SolverBuilder {
+    tables: Vec<String>,
+    /// and the metric they're lower bounds in
+    heuristics: Option<(Metric, Vec<Box<dyn DynHeuristic>>)>,
+    options: TableOptions,
+    metric: Metric,
+    max_depth: u8,
+    threads: usize,
+    time_limit: Option<Duration>,
+    max_nodes: Option<u64>,
+    observer: Option<Observer>,
+    progress: Option<Progress>,
+}
+
+impl Default for SolverBuilder {
+    fn default() -> Self {
+        SolverBuilder {
+            tables: DEFAULT_TABLES.map(str::to_string).to_vec(),
+            heuristics: None,
+            options: TableOptions::default(),
+            metric: Metric::Htm,
+            max_depth: DEFAULT_MAX_DEPTH,
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            time_limit: None,
+            max_nodes: None,
+            observer: None,
+            progress: None,
+        }
+    }
+}
+
+impl SolverBuilder {
+    /// where table files are read from and written to, the working directory by default
+    pub fn table_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.options.dir = dir.into();
+        self
+    }
+
+    /// the tables whose largest bound prunes the search, names from `TABLE_NAMES` optionally
+    /// followed by `@inverse` or `@sym<n>`. Korf's corner and two 6 edge tables by default
+    pub fn tables<I: IntoIterator<Item: Into<String>>>(mut self, tables: I) -> Self {
+        self.tables = tables.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.options.encoding = encoding;
+        self
+    }
+
+    /// map byte tables instead of reading them
+    pub fn mmap(mut self, mmap: bool) -> Self {
+        self.options.mmap = mmap;
+        self
+    }
+
+    /// use the symmetry reduced corner table
+    pub fn symmetry(mut self, symmetry: bool) -> Self {
+        self.options.symmetry = symmetry;
+        self
+    }
+
+    /// the metric solutions are optimal in
+    pub fn metric(mut self, metric: Metric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    /// the largest solution cost searched for
+    pub fn max_depth(mut self, max_depth: u8) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// bound the search with these, at most `MAX_HEURISTICS`, instead of loading `tables`. They
+    /// have to be lower bounds in `metric`, which has to be the solver's. A bound in another
+    /// metric can be more than a solution costs, like half turn edge tables in the slice turn
+    /// metric, and the solutions found wouldn't be optimal
+    pub fn heuristics(mut self, metric: Metric, heuristics: Vec<Box<dyn DynHeuristic>>) -> Self {
+        self.heuristics = Some((metric, heuristics));
+        self
+    }
+
+    /// how long a solve can take before it gives up
+    pub fn time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    /// how many nodes a solve can search before it gives up
+    pub fn max_nodes(mut self, max_nodes: u64) -> Self {
+        self.max_nodes = Some(max_nodes);
+        self
+    }
+
+    /// receives the events of each search, for showing live progress
+    pub fn observer(mut self, observer: Observer) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// receives the diagnostics of loading tables and of each search. Without one they're dropped
+    pub fn progress(mut self, callback: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Arc::new(callback));
+        self
+    }
+
+    pub fn build(self) -> Result<Solver, SolverError> {
+        let progress = self.progress.unwrap_or_else(|| Arc::new(|_| {}));
+        let heuristics = match self.heuristics {
+            Some((_, heuristics)) if heuristics.len() > MAX_HEURISTICS => {
+                return Err(SolverError::Tables(format!(
+                    "A solver can have at most {MAX_HEURISTICS} heuristics"
+                )));
+            }
+            Some((metric, _)) if metric != self.metric => {
+                return Err(SolverError::Tables(format!(
+                    "The heuristics are bounds in {metric:?}, not the solver's {:?}",
+                    self.metric
+                )));
+            }
+            Some((_, heuristics)) => heuristics,
+            None => with_progress(progress.clone(), || {
+                load_heuristics(&self.tables, self.metric, &self.options)
+            })
+            .map_err(SolverError::Tables)?,
+        };
+
+        Ok(Solver {
+            heuristics,
+            metric: self.metric,
+            max_depth: self.max_depth,
+            threads: self.threads,
+            time_limit: self.time_limit,
+            max_nodes: self.max_nodes,
+            observer: self.observer,
+            progress,
+        })
+    }
+}
+
+/// an optimal solver with its tables loaded, which can solve any number of cubes
+pub struct Solver {
+    heuristics: Vec<Box<dyn DynHeuristic>>,
+    metric: Metric,
+    max_depth: u8,
+    threads: usize,
+    time_limit: Option<Duration>,
+    max_nodes: Option<u64>,
+    observer: Option<Observer>,
+    progress: Progress,
+}
+
+/// an optimal solution and what it took to find it
+#[derive(Debug, Clone)]
+pub struct SolveResult {
+    pub solution: Algorithm,
+    /// the cost of the solution in the solver's metric
+    pub depth: u8,
+    pub nodes: u64,
+    pub elapsed: Duration,
+}
+
+#[derive(Debug)]
+pub enum SolverError {
+    /// a table name or option that can't be used, from `build`
+    Tables(String),
+    InvalidCube(CubeError),
+    NoSolution {
+        max_depth: u8,
+    },
+    /// a time or node limit or the cancel token stopped the search
+    Aborted {
+        reason: AbortReason,
+        nodes: u64,
+        /// every solution costs at least this much
+        lower_bound: u8,
+    },
+}
+
+impl std::fmt::Display for SolverError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolverError::Tables(e) => write!(f, "{e}"),
+            SolverError::InvalidCube(e) => write!(f, "invalid cube: {e}"),
+            SolverError::NoSolution { max_depth } => {
+                write!(f, "no solution of up to {max_depth} moves")
+            }
+            SolverError::Aborted {
+                reason,
+                nodes,
+                lower_bound,
+            } => write!(
+                f,
+                "the search {reason} after searching {nodes} nodes, every solution is at least {lower_bound} moves"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SolverError {}
+
+impl Solver {
+    pub fn builder() -> SolverBuilder {
+        SolverBuilder::default()
+    }
+
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    /// the largest bound of the solver's tables, for searches of the caller's own
+    pub fn heuristic(&self) -> HeuristicSet<'_> {
+        HeuristicSet::new(&self.heuristics)
+    }
+
+    /// the limits `solve` searches within, starting the time limit now. Add a cancel token to
+    /// them to stop a single solve from another thread, see `solve_with`
+    pub fn limits(&self) -> SearchLimits {
+        let limits = SearchLimits {
+            observer: self.observer.clone(),
+            ..SearchLimits::default()
+        };
+        let limits = match self.time_limit {
+            Some(time_limit) => limits.time_limit(time_limit),
+            None => limits,
+        };
+        match self.max_nodes {
+            Some(max_nodes) => limits.max_nodes(max_nodes),
+            None => limits,
+        }
+    }
+
+    /// solves every cube like `solve`, `jobs` at a time sharing the solver's threads and tables,
+    /// until `cancel` is cancelled. The cubes have to be valid, see `batch::solve_batch`
+    pub fn solve_batch(
+        &self,
+        cubes: &[Cube],
+        jobs: usize,
+        cancel: Option<CancelToken>,
+        on_result: impl FnMut(usize, &BatchResult),
+    ) -> Vec<BatchResult> {
+        let options = BatchOptions {
+            metric: self.metric,
+            max_depth: self.max_depth,
+            jobs,
+            threads: (self.threads / jobs.max(1)).max(1),
+            time_limit: self.time_limit,
+            max_nodes: self.max_nodes,
+            cancel,
+        };
+        solve_batch(cubes, &options, self.heuristic(), on_result)
+    }
+
+    pub fn solve(&self, cube: &Cube) -> Result<SolveResult, SolverError> {
+        self.solve_with(cube, &self.limits())
+    }
+
+    /// solves within `limits` instead of the solver's own time and node limits and observer
+    pub fn solve_with(
+        &self,
+        cube: &Cube,
+        limits: &SearchLimits,
+    ) -> Result<SolveResult, SolverError> {
+        // an unsolvable state would leave the search running until the maximum depth
+        cube.validate().map_err(SolverError::InvalidCube)?;
+
+        let start = Instant::now();
+        let result = with_progress(self.progress.clone(), || {
+            par_ida(
+                cube.normalised(),
+                self.max_depth,
+                self.metric,
+                self.threads,
+                limits,
+                HeuristicSet::new(&self.heuristics),
+            )
+        });
+
+        match result.outcome {
+            SearchOutcome::Solved(path) => {
+                let solution = Algorithm::from(path);
+                Ok(SolveResult {
+                    depth: solution.length(self.metric) as u8,
+                    solution,
+                    nodes: result.nodes,
+                    elapsed: start.elapsed(),
+                })
+            }
+            SearchOutcome::NoSolution => Err(SolverError::NoSolution {
+                max_depth: self.max_depth,
+            }),
+            SearchOutcome::Aborted(reason) => Err(SolverError::Aborted {
+                reason,
+                nodes: result.nodes,
+                lower_bound: result.lower_bound,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heuristics::Owned;
+    use crate::pruning_table::{CornerOrientation, EO, PruningTable};
+    use crate::puzzle::Puzzle;
+
+    fn small_solver() -> SolverBuilder {
+        Solver::builder()
+            .heuristics(
+                Metric::Htm,
+                vec![
+                    Box::new(Owned(PruningTable::<Cube, CornerOrientation>::generate())),
+                    Box::new(Owned(PruningTable::<Cube, EO>::generate())),
+                ],
+            )
+            .threads(2)
+    }
+
+    #[test]
+    fn solves() {
+        let solver = small_solver().build().unwrap();
+        for scramble in ["R U F", "L2 D' B", "x R U F"] {
+            let cube = scramble.parse::<Algorithm>().unwrap().to_cube();
+            let result = solver.solve(&cube).unwrap();
+            assert_eq!(result.depth, 3);
+            assert!((cube * result.solution.to_cube()).normalised().is_solved());
+        }
+
+        let cubes: Vec<Cube> = ["R U", "F2"]
+            .map(|s| s.parse::<Algorithm>().unwrap().to_cube())
+            .to_vec();
+        let depths: Vec<usize> = solver
+            .solve_batch(&cubes, 2, None, |_, _| {})
+            .iter()
+            .map(|r| r.solution.as_ref().unwrap().0.len())
+            .collect();
+        assert_eq!(depths, [2, 1]);
+    }
+
+    #[test]
+    fn build_errors() {
+        let build = |builder: SolverBuilder| builder.build().err().map(|e| e.to_string());
+
+        assert_eq!(
+            build(Solver::builder().tables(["corner", "edges"])),
+            Some("Unknown pruning table edges, expected one of corner, first6edges, last6edges, first7edges, last7edges".to_string())
+        );
+        assert_eq!(
+            build(Solver::builder().tables(Vec::<String>::new())),
+            Some("No pruning tables listed".to_string())
+        );
+        assert!(matches!(
+            Solver::builder()
+                .encoding(Encoding::Mod3)
+                .metric(Metric::Qtm)
+                .build(),
+            Err(SolverError::Tables(_))
+        ));
+
+        // half turn bounds overestimate in the slice turn metric
+        assert_eq!(
+            build(small_solver().metric(Metric::Stm)),
+            Some("The heuristics are bounds in Htm, not the solver's Stm".to_string())
+        );
+    }
+
+    #[test]
+    fn cancel_one_solve() {
+        let solver = small_solver().build().unwrap();
+        let cube = "R U F D2".parse::<Algorithm>().unwrap().to_cube();
+
+        let token = CancelToken::new();
+        token.cancel();
+        assert!(matches!(
+            solver.solve_with(&cube, &solver.limits().cancel(token)),
+            Err(SolverError::Aborted {
+                reason: AbortReason::Cancelled,
+                ..
+            })
+        ));
+        // the token only belonged to that solve
+        assert_eq!(solver.solve(&cube).unwrap().depth, 4);
+        assert!(matches!(
+            solver.solve_with(&cube, &SearchLimits::default().max_nodes(1)),
+            Err(SolverError::Aborted {
+                reason: AbortReason::Nodes,
+                ..
+            })
+        ));
+    }
+}
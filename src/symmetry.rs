@@ -0,0 +1,491 @@
+use std::marker::PhantomData;
+
+use crate::cube::{Cube, rotation_table};
+use crate::heuristics::Heuristic;
+use crate::mv::Metric;
+use crate::progress::progress;
+use crate::pruning_table::{
+    Coordinate, CornerOrientation, CornerPermutation, SlicePermutation, UDEdgePermutation, UDSlice,
+    generating_moves,
+};
+use crate::puzzle::Puzzle;
+
+/// a way of holding the cube, optionally reflected through the plane between L and R. Two
+/// states that look the same when one of them is held differently take as many moves to solve
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symmetry {
+    /// index into the rotation table
+    rotation: u8,
+    mirror: bool,
+}
+
+impl Symmetry {
+    /// every rotation and reflection of the cube
+    pub const ALL: [Symmetry; 48] = with_mirrors(rotation_range());
+
+    /// the symmetries that keep the U and D faces on the U-D axis, the y rotations and a flip
+    /// around the F-B axis, each with and without a reflection
+    pub const UD: [Symmetry; 16] = with_mirrors([0, 1, 2, 3, 8, 9, 10, 11]);
+
+    pub fn is_mirror(self) -> bool {
+        self.mirror
+    }
+}
+
+const fn rotation_range() -> [u8; 24] {
+    let mut rotations = [0; 24];
+    let mut i = 0;
+    while i < 24 {
+        rotations[i] = i as u8;
+        i += 1;
+    }
+    rotations
+}
+
+const fn with_mirrors<const N: usize, const M: usize>(rotations: [u8; N]) -> [Symmetry; M] {
+    assert!(M == 2 * N);
+    let mut symmetries = [Symmetry {
+        rotation: 0,
+        mirror: false,
+    }; M];
+    let mut i = 0;
+    while i < N {
+        symmetries[2 * i].rotation = rotations[i];
+        symmetries[2 * i + 1] = Symmetry {
+            rotation: rotations[i],
+            mirror: true,
+        };
+        i += 1;
+    }
+    symmetries
+}
+
+// the reflection swapping L and R is its own inverse, it swaps these pieces and centers
+const MIRROR_CORNERS: [u8; 8] = [1, 0, 3, 2, 5, 4, 7, 6];
+const MIRROR_EDGES: [u8; 12] = [0, 3, 2, 1, 5, 4, 7, 6, 8, 11, 10, 9];
+const MIRROR_CENTERS: [u8; 6] = [0, 1, 3, 2, 4, 5];
+
+impl Cube {
+    /// the state as seen after holding the cube the way `sym` does. Turning a face of the
+    /// conjugated state is the same as turning the face `sym` moves there, so both states take as
+    /// many moves to solve
+    pub fn conjugate(&self, sym: Symmetry) -> Cube {
+        let (rotation, inverse) = &rotation_table()[sym.rotation as usize];
+        match sym.mirror {
+            true => &(inverse * &self.mirrored()) * rotation,
+            false => &(inverse * self) * rotation,
+        }
+    }
+
+    /// the reflection through the plane between L and R. The corner stickers are read in the
+    /// opposite direction in a mirror, so twists change direction. The edges' reference stickers
+    /// all stay on the same faces, so flips don't change
+    fn mirrored(&self) -> Cube {
+        Cube {
+            ep: MIRROR_EDGES.map(|i| MIRROR_EDGES[self.ep[i as usize] as usize]),
+            eo: MIRROR_EDGES.map(|i| self.eo[i as usize]),
+            cp: MIRROR_CORNERS.map(|i| MIRROR_CORNERS[self.cp[i as usize] as usize]),
+            co: MIRROR_CORNERS.map(|i| (3 - self.co[i as usize]) % 3),
+            centers: MIRROR_CENTERS.map(|i| MIRROR_CENTERS[self.centers[i as usize] as usize]),
+        }
+    }
+}
+
+/// a coordinate that's still well defined after a state is conjugated by its symmetries, states
+/// with the same coordinate have conjugates with the same coordinate. It's made of a `Reduced`
+/// part, whose symmetry classes are found once and kept in tables, and the `Rest`, which is
+/// conjugated along with it. Both parts have to be well defined on their own
+pub trait SymCoordinate: Coordinate<Cube> {
+    const SYMMETRIES: &'static [Symmetry];
+    type Reduced: Coordinate<Cube>;
+    type Rest: Coordinate<Cube>;
+}
+
+impl SymCoordinate for (CornerOrientation, CornerPermutation) {
+    // the rotations that move U and D elsewhere twist the corners depending on where they are,
+    // so the orientations are only well defined on their own under these
+    const SYMMETRIES: &'static [Symmetry] = &Symmetry::UD;
+    type Reduced = CornerPermutation;
+    type Rest = CornerOrientation;
+}
+
+impl SymCoordinate for (CornerOrientation, UDSlice) {
+    const SYMMETRIES: &'static [Symmetry] = &Symmetry::UD;
+    type Reduced = CornerOrientation;
+    type Rest = UDSlice;
+}
+
+/// the edges of the domino subgroup, whose symmetries keep the E slice edges in the E slice
+impl SymCoordinate for (UDEdgePermutation, SlicePermutation) {
+    const SYMMETRIES: &'static [Symmetry] = &Symmetry::UD;
+    type Reduced = UDEdgePermutation;
+    type Rest = SlicePermutation;
+}
+
+/// the symmetry classes of the `Reduced` part of `T`, and how the `Rest` is conjugated, so a state's
+/// entry is found with a few lookups instead of conjugating it by every symmetry
+struct SymClasses<T: SymCoordinate> {
+    /// the class of each `Reduced` coordinate
+    class: Box<[u32]>,
+    /// for each `Reduced` coordinate, the index in `T::SYMMETRIES` of a symmetry that conjugates it
+    /// to its class's representative. Representatives are conjugated by the identity
+    sym: Box<[u8]>,
+    /// the smallest coordinate in each class
+    representatives: Box<[u32]>,
+    /// a bit for each symmetry that leaves a class's representative as it is, other than the
+    /// identity. The `Rest` of a state in such a class could be conjugated by any of them, the
+    /// smallest result is used so the conjugates of a state share one entry
+    stabilizers: Box<[u64]>,
+    /// each `Rest` coordinate conjugated by each symmetry, at `rest * T::SYMMETRIES.len() + sym`
+    conjugated: Box<[u32]>,
+    coordinate: PhantomData<fn() -> T>,
+}
+
+impl<T: SymCoordinate> SymClasses<T> {
+    /// coordinates are visited in order, so the first one not yet seen in a class is its smallest
+    fn new() -> Self {
+        let symmetries = T::SYMMETRIES;
+        let conjugate = |state: &Cube, sym: Symmetry| T::Reduced::to_coord(&state.conjugate(sym));
+
+        let mut class = vec![u32::MAX; T::Reduced::MAX];
+        let mut sym = vec![0; T::Reduced::MAX];
+        let mut representatives = Vec::new();
+        let mut stabilizers = Vec::new();
+        for coord in 0..T::Reduced::MAX {
+            if class[coord] != u32::MAX {
+                continue;
+            }
+
+            let state = T::Reduced::from_coord(coord);
+            let mut stabilizer = 0;
+            for (i, &symmetry) in symmetries.iter().enumerate() {
+                let conjugate = conjugate(&state, symmetry);
+                if i != 0 && conjugate == coord {
+                    stabilizer |= 1 << i;
+                }
+                if class[conjugate] == u32::MAX {
+                    class[conjugate] = representatives.len() as u32;
+                    // the inverse of `symmetry` takes the conjugate back to `coord`
+                    sym[conjugate] = symmetries
+                        .iter()
+                        .position(|&inverse| {
+                            T::Reduced::to_coord(
+                                &T::Reduced::from_coord(conjugate).conjugate(inverse),
+                            ) == coord
+                        })
+                        .unwrap() as u8;
+                }
+                debug_assert!(
+                    i != 0 || conjugate == coord,
+                    "the first symmetry should be the identity"
+                );
+            }
+            representatives.push(coord as u32);
+            stabilizers.push(stabilizer);
+        }
+
+        let conjugated = (0..T::Rest::MAX)
+            .flat_map(|rest| {
+                let state = T::Rest::from_coord(rest);
+                symmetries
+                    .iter()
+                    .map(move |&sym| T::Rest::to_coord(&state.conjugate(sym)) as u32)
+            })
+            .collect();
+
+        SymClasses {
+            class: class.into(),
+            sym: sym.into(),
+            representatives: representatives.into(),
+            stabilizers: stabilizers.into(),
+            conjugated,
+            coordinate: PhantomData,
+        }
+    }
+
+    /// how many entries a table of `T` has
+    fn entries(&self) -> usize {
+        self.representatives.len() * T::Rest::MAX
+    }
+
+    /// the entry of the states with these coordinates
+    fn index(&self, reduced: usize, rest: usize) -> usize {
+        let n = T::SYMMETRIES.len();
+        let class = self.class[reduced] as usize;
+        let mut rest = self.conjugated[rest * n + self.sym[reduced] as usize] as usize;
+
+        let start = rest;
+        let mut stabilizer = self.stabilizers[class];
+        while stabilizer != 0 {
+            let sym = stabilizer.trailing_zeros() as usize;
+            rest = rest.min(self.conjugated[start * n + sym] as usize);
+            stabilizer &= stabilizer - 1;
+        }
+        class * T::Rest::MAX + rest
+    }
+
+    /// a state whose entry is `index`
+    fn state(&self, index: usize) -> Cube {
+        let representative = self.representatives[index / T::Rest::MAX];
+        let mut state = T::Reduced::from_coord(representative as usize);
+        T::Rest::set_coord(&mut state, index % T::Rest::MAX);
+        state
+    }
+}
+
+/// a pruning table with one entry per symmetry class of the `Reduced` part of the coordinate and
+/// `Rest` coordinate, up to `T::SYMMETRIES.len()` times smaller. A lookup is a few table lookups
+/// more than a full table's
+pub struct SymPruningTable<T: SymCoordinate> {
+    classes: SymClasses<T>,
+    costs: Box<[u8]>,
+}
+
+impl<T: SymCoordinate> SymPruningTable<T> {
+    pub fn generate() -> Self {
+        Self::generate_for(Metric::Htm)
+    }
+
+    /// the same weighted breadth first search as `PruningTable::generate_for`, over classes
+    pub fn generate_for(metric: Metric) -> Self {
+        progress!(
+            "Generating {metric:?} symmetry reduced pruning table for {}",
+            std::any::type_name::<T>()
+        );
+
+        const UNSET: u8 = u8::MAX;
+        let start = std::time::Instant::now();
+
+        let classes = SymClasses::<T>::new();
+        progress!(
+            "Found {} classes of {} coordinates",
+            classes.representatives.len(),
+            T::Reduced::MAX
+        );
+
+        let mut costs = vec![UNSET; classes.entries()];
+        let moves = generating_moves::<Cube, T>(metric);
+
+        let solved = Cube::solved();
+        costs[classes.index(T::Reduced::to_coord(&solved), T::Rest::to_coord(&solved))] = 0;
+        let mut total_filled = 1;
+        let mut highest = 0;
+
+        for cost in 0.. {
+            progress!("Generating depth {}, {total_filled} filled", cost + 1);
+
+            for index in 0..costs.len() {
+                if costs[index] != cost {
+                    continue;
+                }
+
+                let reduced = classes.representatives[index / T::Rest::MAX] as usize;
+                let rest = index % T::Rest::MAX;
+                let mut puzzle = None;
+                for &mv in moves {
+                    // through move tables where the coordinates have them
+                    let turned = T::Reduced::turn(reduced, mv).zip(T::Rest::turn(rest, mv));
+                    let (reduced, rest) = turned.unwrap_or_else(|| {
+                        let puzzle = puzzle.get_or_insert_with(|| classes.state(index));
+                        let turned = (puzzle.clone() * mv).reoriented();
+                        (T::Reduced::to_coord(&turned), T::Rest::to_coord(&turned))
+                    });
+                    let new_index = classes.index(reduced, rest);
+                    let new_cost = cost + mv.cost(metric);
+
+                    if costs[new_index] == UNSET {
+                        total_filled += 1;
+                    }
+                    if new_cost < costs[new_index] {
+                        costs[new_index] = new_cost;
+                        highest = highest.max(new_cost);
+                    }
+                }
+            }
+
+            if total_filled >= costs.len() {
+                progress!("Filled all entries in the table, stopping at depth {highest}");
+                break;
+            }
+
+            if cost >= highest {
+                progress!("No new entries after depth {cost}, stopping");
+                break;
+            }
+        }
+
+        progress!("time taken to generate lookup table: {:?}", start.elapsed());
+        SymPruningTable {
+            classes,
+            costs: costs.into(),
+        }
+    }
+
+    pub fn classes(&self) -> usize {
+        self.classes.representatives.len()
+    }
+
+    /// the costs, a byte per entry. The classes are found again when the table's read
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.costs.to_vec()
+    }
+
+    /// `None` when `bytes` can't have been written by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let classes = SymClasses::new();
+        (bytes.len() == classes.entries()).then(|| SymPruningTable {
+            classes,
+            costs: bytes.into(),
+        })
+    }
+}
+
+impl<T: SymCoordinate> Heuristic<Cube> for &SymPruningTable<T> {
+    type Memo = u8;
+
+    fn lower_bound(self, state: &Cube) -> u8 {
+        let index = self
+            .classes
+            .index(T::Reduced::to_coord(state), T::Rest::to_coord(state));
+        self.costs[index]
+    }
+
+    fn memo(self, state: &Cube) -> u8 {
+        self.lower_bound(state)
+    }
+
+    fn next_memo(self, state: &Cube, _parent: u8) -> u8 {
+        self.lower_bound(state)
+    }
+
+    fn memo_bound(self, memo: u8) -> u8 {
+        memo
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{Algorithm, Mirror};
+    use crate::cube::SOLVED;
+    use crate::mv::Move::{self, *};
+    use crate::pruning_table::PruningTable;
+
+    fn scrambles() -> impl Iterator<Item = Cube> {
+        [
+            "R U2 F' L B D R",
+            "F2 D' L U B2 R' F",
+            "U R2 B L' D2 F R' U2 L",
+            "B' D2 R F' U L2",
+        ]
+        .into_iter()
+        .map(|s| s.parse::<Algorithm>().unwrap().to_cube())
+    }
+
+    #[test]
+    fn conjugation() {
+        let mirror = Symmetry::ALL[1];
+        assert!(mirror.is_mirror());
+
+        for scramble in ["R U R' U'", "F2 D' L U B2 R' F", "M2 E S'"] {
+            let alg = scramble.parse::<Algorithm>().unwrap();
+            assert_eq!(
+                alg.to_cube().conjugate(mirror),
+                alg.mirror(Mirror::LeftRight).to_cube()
+            );
+        }
+
+        for sym in Symmetry::ALL {
+            // face turns are conjugated to face turns, and conjugating respects composition
+            for &mv in Move::ALL {
+                let turned = mv.to_cube().conjugate(sym);
+                assert!(Move::ALL.iter().any(|m| m.to_cube() == turned));
+            }
+            for scramble in scrambles() {
+                let twice = (&scramble * &scramble).conjugate(sym);
+                let conjugate = scramble.conjugate(sym);
+                assert_eq!(twice, &conjugate * &conjugate);
+                assert!(conjugate.validate().is_ok());
+            }
+            assert_eq!(SOLVED.conjugate(sym), SOLVED);
+        }
+
+        for sym in Symmetry::UD {
+            let turned = U.to_cube().conjugate(sym);
+            assert!(
+                Move::ALL
+                    .iter()
+                    .any(|m| m.axis() == U.axis() && m.to_cube() == turned)
+            );
+        }
+    }
+
+    /// both parts of `T` give states with the same coordinate conjugates with the same coordinate
+    fn assert_well_defined<T: SymCoordinate>(scrambles: impl Iterator<Item = Cube>) {
+        fn part<C: Coordinate<Cube>>(scramble: &Cube, symmetries: &[Symmetry]) {
+            let same = C::from_coord(C::to_coord(scramble));
+            for &sym in symmetries {
+                assert_eq!(
+                    C::to_coord(&scramble.conjugate(sym)),
+                    C::to_coord(&same.conjugate(sym)),
+                    "{} {sym:?}",
+                    std::any::type_name::<C>()
+                );
+            }
+        }
+
+        for scramble in scrambles {
+            part::<T::Reduced>(&scramble, T::SYMMETRIES);
+            part::<T::Rest>(&scramble, T::SYMMETRIES);
+        }
+    }
+
+    fn domino_scrambles() -> impl Iterator<Item = Cube> {
+        [
+            "R2 U F2 D' L2 B2 U2",
+            "U' B2 D R2 F2 U L2 D2",
+            "D2 L2 U' F2 R2 B2 D U",
+        ]
+        .into_iter()
+        .map(|s| s.parse::<Algorithm>().unwrap().to_cube())
+    }
+
+    #[test]
+    fn coordinates_are_well_defined() {
+        assert_well_defined::<(CornerOrientation, CornerPermutation)>(scrambles());
+        assert_well_defined::<(CornerOrientation, UDSlice)>(scrambles());
+        assert_well_defined::<(UDEdgePermutation, SlicePermutation)>(domino_scrambles());
+    }
+
+    #[test]
+    fn matches_full_table() {
+        type Edges = (UDEdgePermutation, SlicePermutation);
+        let full = PruningTable::<Cube, Edges>::generate();
+        let reduced = SymPruningTable::<Edges>::generate();
+        assert!(reduced.classes() * 16 >= UDEdgePermutation::MAX);
+        assert!(reduced.classes() < UDEdgePermutation::MAX / 8);
+
+        // every state conjugated by every symmetry finds its class's entry
+        for scramble in domino_scrambles() {
+            for sym in Symmetry::UD {
+                let state = scramble.conjugate(sym);
+                assert_eq!(
+                    (&reduced).lower_bound(&state),
+                    full.as_ref().lower_bound(&state)
+                );
+            }
+        }
+        for coord in (0..Edges::MAX).step_by(97) {
+            let state = Edges::from_coord(coord);
+            assert_eq!(
+                (&reduced).lower_bound(&state),
+                full.as_ref().lower_bound(&state)
+            );
+        }
+
+        let bytes = reduced.to_bytes();
+        let loaded = SymPruningTable::<Edges>::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.to_bytes(), bytes);
+        assert!(SymPruningTable::<Edges>::from_bytes(&bytes[1..]).is_none());
+    }
+}
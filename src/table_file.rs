@@ -0,0 +1,356 @@
+use std::io::{Read, Write};
+use std::path::Path;
+
+use crate::algorithm::Algorithm;
+use crate::mv::{Metric, Move};
+use crate::pruning_table::Coordinate;
+
+/// the start of every table file
+const MAGIC: [u8; 8] = *b"CUBETABL";
+/// bumped whenever the layout of the header or of any table's data changes
+pub const VERSION: u32 = 1;
+
+/// what a table file has to have been generated for to be used, written in its header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableKind {
+    /// `T::name()`
+    pub coordinate: String,
+    /// the number of coordinates, `T::MAX`
+    pub size: u64,
+    pub encoding: String,
+    pub metric: Metric,
+    /// the moves the table was generated with
+    pub moves: String,
+}
+
+impl TableKind {
+    pub fn new<S, T: Coordinate<S>>(encoding: &str, metric: Metric, moves: &[Move]) -> Self {
+        TableKind {
+            coordinate: T::name(),
+            size: T::MAX as u64,
+            encoding: encoding.to_string(),
+            metric,
+            moves: Algorithm::from(moves.to_vec()).to_string(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum TableFileError {
+    Io(std::io::Error),
+    /// not a table file, or one written before table files had headers
+    Magic,
+    /// written by a different version of the format
+    Version(u32),
+    /// the header is cut short or isn't valid utf-8
+    Header,
+    /// the table was generated for something else
+    Mismatch {
+        field: &'static str,
+        expected: String,
+        found: String,
+    },
+    /// the data is a different length from the one in the header, usually a truncated file
+    Length {
+        expected: u64,
+        found: u64,
+    },
+    Checksum {
+        expected: u64,
+        found: u64,
+    },
+}
+
+impl std::fmt::Display for TableFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TableFileError::Io(e) => write!(f, "{e}"),
+            TableFileError::Magic => write!(f, "not a pruning table file"),
+            TableFileError::Version(version) => {
+                write!(f, "format version {version}, expected {VERSION}")
+            }
+            TableFileError::Header => write!(f, "the header can't be read"),
+            TableFileError::Mismatch {
+                field,
+                expected,
+                found,
+            } => write!(f, "generated for {field} {found}, expected {expected}"),
+            TableFileError::Length { expected, found } => {
+                write!(f, "{found} bytes of data, expected {expected}")
+            }
+            TableFileError::Checksum { expected, found } => {
+                write!(f, "checksum {found:016x}, expected {expected:016x}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TableFileError {}
+
+impl From<std::io::Error> for TableFileError {
+    fn from(e: std::io::Error) -> Self {
+        TableFileError::Io(e)
+    }
+}
+
+/// 64 bit FNV-1a, enough to notice files that were damaged or edited
+pub fn checksum(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// writes the header for `kind` followed by `data`. The file is written next to `path` then moved
+/// over it, so processes that have the old file mapped keep seeing the old file
+pub fn write_table(path: impl AsRef<Path>, kind: &TableKind, data: &[u8]) -> std::io::Result<()> {
+    let path = path.as_ref();
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    let mut file = std::io::BufWriter::new(std::fs::File::create(&partial)?);
+
+    file.write_all(&MAGIC)?;
+    file.write_all(&VERSION.to_le_bytes())?;
+    for field in [
+        &kind.coordinate,
+        &kind.encoding,
+        &format!("{:?}", kind.metric),
+        &kind.moves,
+    ] {
+        file.write_all(&(field.len() as u32).to_le_bytes())?;
+        file.write_all(field.as_bytes())?;
+    }
+    file.write_all(&kind.size.to_le_bytes())?;
+    file.write_all(&(data.len() as u64).to_le_bytes())?;
+    file.write_all(&checksum(data).to_le_bytes())?;
+    file.write_all(data)?;
+
+    file.flush()?;
+    drop(file);
+
+    std::fs::rename(partial, path)
+}
+
+/// the data of the table at `path`, once its header is checked against `kind` and its checksum
+/// against the data
+pub fn read_table(path: impl AsRef<Path>, kind: &TableKind) -> Result<Vec<u8>, TableFileError> {
+    let mut bytes = std::fs::read(path)?;
+    let (header, expected) = check_header(&bytes, kind)?;
+
+    let found = checksum(&bytes[header..]);
+    if found != expected {
+        return Err(TableFileError::Checksum { expected, found });
+    }
+
+    bytes.drain(..header);
+    Ok(bytes)
+}
+
+/// checks everything in the header apart from the checksum, which needs all of the data read.
+/// Returns the length of the header and the checksum
+pub(crate) fn check_header(bytes: &[u8], kind: &TableKind) -> Result<(usize, u64), TableFileError> {
+    let (header, len) = parse_header(bytes)?;
+
+    let expected = [
+        ("coordinate", &kind.coordinate, &header.coordinate),
+        ("encoding", &kind.encoding, &header.encoding),
+        ("metric", &format!("{:?}", kind.metric), &header.metric),
+        ("moves", &kind.moves, &header.moves),
+        ("size", &kind.size.to_string(), &header.size.to_string()),
+    ];
+    for (field, expected, found) in expected {
+        if found != expected {
+            return Err(TableFileError::Mismatch {
+                field,
+                expected: expected.clone(),
+                found: found.clone(),
+            });
+        }
+    }
+
+    let found = (bytes.len() - len) as u64;
+    if found != header.length {
+        return Err(TableFileError::Length {
+            expected: header.length,
+            found,
+        });
+    }
+
+    Ok((len, header.checksum))
+}
+
+/// what a table file says it holds, as written in its header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableHeader {
+    pub coordinate: String,
+    pub encoding: String,
+    pub metric: String,
+    pub moves: String,
+    pub size: u64,
+    /// the length of the data after the header
+    pub length: u64,
+    pub checksum: u64,
+}
+
+/// the header of the table at `path`, without reading its data
+pub fn read_header(path: impl AsRef<Path>) -> Result<TableHeader, TableFileError> {
+    // far longer than any header, which is mostly the coordinate's type name
+    const LIMIT: u64 = 1 << 16;
+    let mut bytes = Vec::new();
+    std::fs::File::open(path)?
+        .take(LIMIT)
+        .read_to_end(&mut bytes)?;
+    Ok(parse_header(&bytes)?.0)
+}
+
+/// the header at the start of `bytes`, and its length
+fn parse_header(bytes: &[u8]) -> Result<(TableHeader, usize), TableFileError> {
+    let mut reader = Reader(bytes);
+
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(TableFileError::Magic);
+    }
+    let version = reader.u32()?;
+    if version != VERSION {
+        return Err(TableFileError::Version(version));
+    }
+
+    let header = TableHeader {
+        coordinate: reader.string()?,
+        encoding: reader.string()?,
+        metric: reader.string()?,
+        moves: reader.string()?,
+        size: reader.u64()?,
+        length: reader.u64()?,
+        checksum: reader.u64()?,
+    };
+    Ok((header, bytes.len() - reader.0.len()))
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], TableFileError> {
+        if self.0.len() < n {
+            return Err(TableFileError::Header);
+        }
+        let (taken, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u32(&mut self) -> Result<u32, TableFileError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, TableFileError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Result<String, TableFileError> {
+        let len = self.u32()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| TableFileError::Header)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Cube;
+    use crate::pruning_table::{CornerOrientation, EO, PartialEdges};
+
+    fn path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("cube_solver_{name}_{}.bin", std::process::id()))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = path("round_trip");
+        let kind = TableKind::new::<Cube, EO>("byte", Metric::Htm, Move::ALL);
+        let data: Vec<u8> = (0..=255).collect();
+
+        write_table(&path, &kind, &data).unwrap();
+        assert_eq!(read_table(&path, &kind).unwrap(), data);
+
+        let header = read_header(&path).unwrap();
+        assert_eq!(header.coordinate, "EO");
+        assert_eq!(header.metric, "Htm");
+        assert_eq!((header.size, header.length), (EO::MAX as u64, 256));
+        assert_eq!(header.checksum, checksum(&data));
+
+        let other = TableKind::new::<Cube, CornerOrientation>("byte", Metric::Htm, Move::ALL);
+        assert!(matches!(
+            read_table(&path, &other),
+            Err(TableFileError::Mismatch {
+                field: "coordinate",
+                ..
+            })
+        ));
+        let qtm = TableKind::new::<Cube, EO>("byte", Metric::Qtm, Move::ALL);
+        assert!(matches!(
+            read_table(&path, &qtm),
+            Err(TableFileError::Mismatch {
+                field: "metric",
+                ..
+            })
+        ));
+
+        // tuples and partial coordinates spell out their parts without module paths
+        let pair = TableKind::new::<Cube, (CornerOrientation, PartialEdges<0, 6>)>(
+            "byte",
+            Metric::Htm,
+            Move::ALL,
+        );
+        assert_eq!(pair.coordinate, "(CornerOrientation, PartialEdges<0, 6>)");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn damaged_files() {
+        let path = path("damaged");
+        let kind = TableKind::new::<Cube, EO>("nibble", Metric::Htm, Move::DOMINO);
+        write_table(&path, &kind, &[1, 2, 3, 4]).unwrap();
+        let file = std::fs::read(&path).unwrap();
+
+        // truncated
+        std::fs::write(&path, &file[..file.len() - 1]).unwrap();
+        assert!(matches!(
+            read_table(&path, &kind),
+            Err(TableFileError::Length {
+                expected: 4,
+                found: 3
+            })
+        ));
+
+        // changed data
+        let mut changed = file.clone();
+        *changed.last_mut().unwrap() = 5;
+        std::fs::write(&path, &changed).unwrap();
+        assert!(matches!(
+            read_table(&path, &kind),
+            Err(TableFileError::Checksum { .. })
+        ));
+
+        // a table from before headers
+        std::fs::write(&path, [0; 2048]).unwrap();
+        assert!(matches!(
+            read_table(&path, &kind),
+            Err(TableFileError::Magic)
+        ));
+
+        std::fs::write(&path, &file[..10]).unwrap();
+        assert!(matches!(
+            read_table(&path, &kind),
+            Err(TableFileError::Header)
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            read_table(&path, &kind),
+            Err(TableFileError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,524 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::cube::Cube;
+use crate::heuristics::{Conjugated, DynHeuristic, Halved, Inverse, MAX_HEURISTICS, Owned};
+use crate::mapped_table::{LoadedTable, MappedTable};
+use crate::mv::{Metric, Move};
+use crate::packed_table::{Encoding, Mod3PruningTable, NibblePruningTable, PackedTable};
+use crate::progress::progress;
+use crate::pruning_table::{
+    Coordinate, CornerOrientation, CornerPermutation, PartialEdges, PruningTable, generating_moves,
+};
+use crate::puzzle::Puzzle;
+use crate::symmetry::{SymCoordinate, SymPruningTable, Symmetry};
+use crate::table_file::{TableFileError, TableKind, read_table, write_table};
+
+pub type Corners = (CornerOrientation, CornerPermutation);
+pub type First6Edges = PartialEdges<0, 6>;
+pub type Last6Edges = PartialEdges<6, 12>;
+pub type First7Edges = PartialEdges<0, 7>;
+pub type Last7Edges = PartialEdges<5, 12>;
+
+/// the pattern databases a heuristic set can list, named like their files
+pub const TABLE_NAMES: [&str; 5] = [
+    "corner",
+    "first6edges",
+    "last6edges",
+    "first7edges",
+    "last7edges",
+];
+
+/// Korf's tables, the corners and both halves of the edges
+pub const DEFAULT_TABLES: [&str; 3] = ["corner", "first6edges", "last6edges"];
+
+/// where table files are kept and how they're stored
+#[derive(Debug, Clone)]
+pub struct TableOptions {
+    pub dir: PathBuf,
+    pub encoding: Encoding,
+    /// map byte tables instead of reading them
+    pub mmap: bool,
+    /// use the symmetry reduced corner table
+    pub symmetry: bool,
+}
+
+impl Default for TableOptions {
+    fn default() -> Self {
+        TableOptions {
+            dir: PathBuf::from("."),
+            encoding: Encoding::Byte,
+            mmap: false,
+            symmetry: false,
+        }
+    }
+}
+
+impl TableOptions {
+    /// the combinations of options, metric and lookups that the tables can't be used with
+    pub fn check(&self, metric: Metric, lookups: &[Lookup]) -> Result<(), &'static str> {
+        // the slice turn metric uses the half turn edge tables, whose costs change by 2 with a slice
+        if self.encoding == Encoding::Mod3 && metric != Metric::Htm {
+            return Err("Mod 3 pruning tables are only supported in the half turn metric");
+        }
+        if self.mmap && self.encoding != Encoding::Byte {
+            return Err("Only tables with a byte per entry can be memory mapped");
+        }
+        if self.symmetry && self.encoding != Encoding::Byte {
+            return Err("The symmetry reduced corner table is only stored a byte per class");
+        }
+        // an inverse state's bound can't be carried from its parent's, and a mod 3 table finds
+        // one from scratch by walking back to solved
+        if self.encoding == Encoding::Mod3 && lookups.contains(&Lookup::Inverse) {
+            return Err("Mod 3 pruning tables can't be looked up on the inverse state");
+        }
+        Ok(())
+    }
+}
+
+/// how a listed table is looked up, from what follows an `@` after its name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lookup {
+    Direct,
+    /// `@inverse`, on the inverse state as well
+    Inverse,
+    /// `@sym<n>`, on the state conjugated by `Symmetry::ALL[n]`
+    Conjugated(Symmetry),
+}
+
+/// splits a listed table into the table and how it's looked up
+pub fn parse_table(entry: &str) -> Result<(&str, Lookup), String> {
+    let (name, lookup) = match entry.split_once('@') {
+        None => (entry, Lookup::Direct),
+        Some((name, "inverse")) => (name, Lookup::Inverse),
+        Some((name, lookup)) => {
+            let sym = lookup
+                .strip_prefix("sym")
+                .and_then(|n| n.parse::<usize>().ok())
+                .and_then(|n| Symmetry::ALL.get(n));
+            match sym {
+                Some(&sym) => (name, Lookup::Conjugated(sym)),
+                None => {
+                    return Err(format!(
+                        "Unknown lookup {lookup} for {name}, expected inverse or sym0 to sym47"
+                    ));
+                }
+            }
+        }
+    };
+
+    if !TABLE_NAMES.contains(&name) {
+        return Err(format!(
+            "Unknown pruning table {name}, expected one of {}",
+            TABLE_NAMES.join(", ")
+        ));
+    }
+    Ok((name, lookup))
+}
+
+/// table names separated by commas, whitespace or new lines, with `#` starting a comment. Each
+/// can be followed by a `Lookup`
+pub fn parse_table_names(list: &str) -> Vec<String> {
+    list.lines()
+        .map(|line| line.split('#').next().unwrap())
+        .flat_map(|line| line.split(|c: char| c == ',' || c.is_whitespace()))
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// loads the listed tables, each once however many ways it's looked up, to bound a search in
+/// `metric`
+pub fn load_heuristics(
+    entries: &[impl AsRef<str>],
+    metric: Metric,
+    options: &TableOptions,
+) -> Result<Vec<Box<dyn DynHeuristic>>, String> {
+    if entries.is_empty() {
+        return Err("No pruning tables listed".to_string());
+    }
+    if entries.len() > MAX_HEURISTICS {
+        return Err(format!(
+            "At most {MAX_HEURISTICS} pruning tables can be listed"
+        ));
+    }
+    let entries = entries
+        .iter()
+        .map(|entry| parse_table(entry.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let lookups: Vec<Lookup> = entries.iter().map(|&(_, lookup)| lookup).collect();
+    options.check(metric, &lookups)?;
+
+    let mut tables: HashMap<&str, Arc<dyn DynHeuristic>> = HashMap::new();
+    let heuristics = entries
+        .into_iter()
+        .map(|(name, lookup)| {
+            let table = tables
+                .entry(name)
+                .or_insert_with(|| load_heuristic(name, metric, options).into())
+                .clone();
+            match lookup {
+                Lookup::Direct => Box::new(table) as Box<dyn DynHeuristic>,
+                Lookup::Inverse => Box::new(Inverse(table)),
+                Lookup::Conjugated(sym) => Box::new(Conjugated(table, sym)),
+            }
+        })
+        .collect();
+    Ok(heuristics)
+}
+
+/// slice turns relabel the edges, so partial edge tables can't be generated in the slice turn
+/// metric and the half turn tables are used at half their value instead
+pub fn edge_metric(metric: Metric) -> Metric {
+    match metric {
+        Metric::Stm => Metric::Htm,
+        metric => metric,
+    }
+}
+
+/// loads one of `TABLE_NAMES`
+fn load_heuristic(name: &str, metric: Metric, options: &TableOptions) -> Box<dyn DynHeuristic> {
+    progress!("Loading {name} pruning table...");
+    let start = std::time::Instant::now();
+
+    let edges = |heuristic: Box<dyn DynHeuristic>| -> Box<dyn DynHeuristic> {
+        match metric {
+            Metric::Stm => Box::new(Halved(heuristic)),
+            _ => heuristic,
+        }
+    };
+    let table_name = format!("{name}_pruning_table");
+    let edge_metric = edge_metric(metric);
+
+    let heuristic = match name {
+        "corner" if options.symmetry => Box::new(Owned(Box::new(load_sym_table::<Corners>(
+            &options.dir,
+            &table_name,
+            metric,
+        )))),
+        "corner" => load_dyn_table::<Corners>(&table_name, metric, options),
+        "first6edges" => edges(load_dyn_table::<First6Edges>(
+            &table_name,
+            edge_metric,
+            options,
+        )),
+        "last6edges" => edges(load_dyn_table::<Last6Edges>(
+            &table_name,
+            edge_metric,
+            options,
+        )),
+        "first7edges" => edges(load_dyn_table::<First7Edges>(
+            &table_name,
+            edge_metric,
+            options,
+        )),
+        "last7edges" => edges(load_dyn_table::<Last7Edges>(
+            &table_name,
+            edge_metric,
+            options,
+        )),
+        _ => unreachable!("{name} was checked to be in TABLE_NAMES"),
+    };
+
+    progress!("Loaded {name} pruning table in {:?}", start.elapsed());
+    heuristic
+}
+
+/// loads a table with any encoding, to be used as one of a set of heuristics
+fn load_dyn_table<T>(name: &str, metric: Metric, options: &TableOptions) -> Box<dyn DynHeuristic>
+where
+    T: Coordinate<Cube> + 'static,
+    [u8; T::MAX]: Sized,
+{
+    let dir = &options.dir;
+    match options.encoding {
+        Encoding::Byte => Box::new(Owned(load_byte_table::<Cube, T>(
+            dir,
+            name,
+            metric,
+            options.mmap,
+        ))),
+        Encoding::Nibble => Box::new(Owned(Box::new(load_packed_table::<
+            Cube,
+            T,
+            NibblePruningTable<Cube, T>,
+        >(dir, name, metric)))),
+        Encoding::Mod3 => Box::new(Owned(Box::new(load_packed_table::<
+            Cube,
+            T,
+            Mod3PruningTable<Cube, T>,
+        >(dir, name, metric)))),
+    }
+}
+
+/// reads a packed table, or packs the full table and writes it when it can't be used
+pub fn load_packed_table<S, T, P>(dir: &Path, name: &str, metric: Metric) -> P
+where
+    S: Puzzle + Clone + std::ops::Mul<Move, Output = S>,
+    T: Coordinate<S>,
+    [u8; T::MAX]: Sized,
+    P: PackedTable<S, T>,
+{
+    let (path, kind) = packed_file::<S, T, P>(dir, name, metric);
+    progress!("Loading {}...", path.display());
+    let start = std::time::Instant::now();
+
+    let table = read_table(&path, &kind).and_then(|data| {
+        let found = data.len() as u64;
+        P::from_bytes(data, metric).ok_or(TableFileError::Length {
+            expected: kind.size,
+            found,
+        })
+    });
+    let table = match table {
+        Ok(table) => table,
+        Err(e) => {
+            progress!("Can't use {}: {e}, regenerating it", path.display());
+            let table = P::pack(&load_pruning_table(dir, name, metric), metric);
+            save(&path, &kind, table.as_bytes());
+            table
+        }
+    };
+
+    progress!("Loaded {} in {:?}", path.display(), start.elapsed());
+    table
+}
+
+/// reads a symmetry reduced table, or generates and writes it when it can't be used
+pub fn load_sym_table<T: SymCoordinate>(
+    dir: &Path,
+    name: &str,
+    metric: Metric,
+) -> SymPruningTable<T> {
+    let (path, kind) = sym_file::<T>(dir, name, metric);
+    progress!("Loading {}...", path.display());
+    let start = std::time::Instant::now();
+
+    let table = read_table(&path, &kind).and_then(|data| {
+        SymPruningTable::from_bytes(&data).ok_or(TableFileError::Length {
+            expected: kind.size,
+            found: data.len() as u64,
+        })
+    });
+    let table = match table {
+        Ok(table) => table,
+        Err(e) => {
+            progress!("Can't use {}: {e}, regenerating it", path.display());
+            let table = SymPruningTable::generate_for(metric);
+            save(&path, &kind, &table.to_bytes());
+            table
+        }
+    };
+
+    progress!("Loaded {} in {:?}", path.display(), start.elapsed());
+    table
+}
+
+/// maps or reads `{name}.bin`, with the metric's suffix, generating it first when it can't be used
+pub fn load_byte_table<S, T>(
+    dir: &Path,
+    name: &str,
+    metric: Metric,
+    mmap: bool,
+) -> LoadedTable<S, T>
+where
+    S: Puzzle + Clone + std::ops::Mul<Move, Output = S>,
+    T: Coordinate<S>,
+    [u8; T::MAX]: Sized,
+{
+    if !mmap {
+        return LoadedTable::Read(load_pruning_table(dir, name, metric));
+    }
+
+    let (path, kind) = byte_file::<S, T>(dir, name, metric);
+    match MappedTable::open(&path, &kind) {
+        Ok(table) => LoadedTable::Mapped(table),
+        Err(e) => {
+            progress!("Can't map {}: {e}", path.display());
+            // writes the table out, the generated copy is dropped once it's mapped
+            let table = load_pruning_table::<S, T>(dir, name, metric);
+            match MappedTable::open(&path, &kind) {
+                Ok(mapped) => LoadedTable::Mapped(mapped),
+                // the table couldn't be written, so it's kept in memory instead
+                Err(_) => LoadedTable::Read(table),
+            }
+        }
+    }
+}
+
+/// reads `{name}.bin`, with the metric's suffix, or generates and writes it when it can't be used
+pub fn load_pruning_table<S, T>(dir: &Path, name: &str, metric: Metric) -> Box<PruningTable<S, T>>
+where
+    S: Puzzle + Clone + std::ops::Mul<Move, Output = S>,
+    T: Coordinate<S>,
+    [u8; T::MAX]: Sized,
+{
+    let (path, kind) = byte_file::<S, T>(dir, name, metric);
+
+    // the header's size is the coordinate's, but the data can still be another length
+    let table = read_table(&path, &kind).and_then(|data| {
+        let found = data.len() as u64;
+        data.try_into()
+            .map(PruningTable::new)
+            .map_err(|_| TableFileError::Length {
+                expected: kind.size,
+                found,
+            })
+    });
+    match table {
+        Ok(table) => table,
+        Err(e) => {
+            progress!("Can't use {}: {e}, regenerating it", path.display());
+            let table = PruningTable::generate_for(metric);
+            save(&path, &kind, (*table).as_ref());
+            table
+        }
+    }
+}
+
+/// the file a table in `TABLE_NAMES` is kept in with these options, and the header it should have
+pub fn table_file(
+    name: &str,
+    metric: Metric,
+    options: &TableOptions,
+) -> Result<(PathBuf, TableKind), String> {
+    let (name, lookup) = parse_table(name)?;
+    options.check(metric, &[lookup])?;
+    let table_name = format!("{name}_pruning_table");
+    let edge_metric = edge_metric(metric);
+
+    Ok(match name {
+        "corner" if options.symmetry => sym_file::<Corners>(&options.dir, &table_name, metric),
+        "corner" => encoded_file::<Corners>(&table_name, metric, options),
+        "first6edges" => encoded_file::<First6Edges>(&table_name, edge_metric, options),
+        "last6edges" => encoded_file::<Last6Edges>(&table_name, edge_metric, options),
+        "first7edges" => encoded_file::<First7Edges>(&table_name, edge_metric, options),
+        "last7edges" => encoded_file::<Last7Edges>(&table_name, edge_metric, options),
+        _ => unreachable!("{name} was checked to be in TABLE_NAMES"),
+    })
+}
+
+fn encoded_file<T>(name: &str, metric: Metric, options: &TableOptions) -> (PathBuf, TableKind)
+where
+    T: Coordinate<Cube>,
+    [u8; T::MAX]: Sized,
+{
+    let dir = &options.dir;
+    match options.encoding {
+        Encoding::Byte => byte_file::<Cube, T>(dir, name, metric),
+        Encoding::Nibble => packed_file::<Cube, T, NibblePruningTable<Cube, T>>(dir, name, metric),
+        Encoding::Mod3 => packed_file::<Cube, T, Mod3PruningTable<Cube, T>>(dir, name, metric),
+    }
+}
+
+/// `{name}.bin`, with the metric's suffix
+fn byte_file<S, T>(dir: &Path, name: &str, metric: Metric) -> (PathBuf, TableKind)
+where
+    S: Puzzle + Clone + std::ops::Mul<Move, Output = S>,
+    T: Coordinate<S>,
+{
+    let path = dir.join(format!("{name}{}.bin", metric.suffix()));
+    let kind = TableKind::new::<S, T>(
+        Encoding::Byte.name(),
+        metric,
+        generating_moves::<S, T>(metric),
+    );
+    (path, kind)
+}
+
+fn packed_file<S, T, P>(dir: &Path, name: &str, metric: Metric) -> (PathBuf, TableKind)
+where
+    S: Puzzle + Clone + std::ops::Mul<Move, Output = S>,
+    T: Coordinate<S>,
+    [u8; T::MAX]: Sized,
+    P: PackedTable<S, T>,
+{
+    let path = dir.join(format!("{name}{}{}.bin", metric.suffix(), P::SUFFIX));
+    let kind = TableKind::new::<S, T>(P::ENCODING.name(), metric, generating_moves::<S, T>(metric));
+    (path, kind)
+}
+
+fn sym_file<T: SymCoordinate>(dir: &Path, name: &str, metric: Metric) -> (PathBuf, TableKind) {
+    let path = dir.join(format!("{name}{}_sym.bin", metric.suffix()));
+    let kind = TableKind::new::<Cube, T>(
+        "symmetry classes",
+        metric,
+        generating_moves::<Cube, T>(metric),
+    );
+    (path, kind)
+}
+
+/// writes a generated table for next time, a table that can't be written is still used
+fn save(path: &Path, kind: &TableKind, data: &[u8]) {
+    if let Err(e) = write_table(path, kind, data) {
+        progress!("Can't write {}: {e}", path.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_lists() {
+        let names = parse_table_names("# korf\ncorner\nfirst6edges, last6edges@inverse # edges\n");
+        assert_eq!(names, ["corner", "first6edges", "last6edges@inverse"]);
+
+        assert_eq!(parse_table("corner"), Ok(("corner", Lookup::Direct)));
+        assert_eq!(
+            parse_table("last6edges@inverse"),
+            Ok(("last6edges", Lookup::Inverse))
+        );
+        assert_eq!(
+            parse_table("first7edges@sym5"),
+            Ok(("first7edges", Lookup::Conjugated(Symmetry::ALL[5])))
+        );
+        assert!(parse_table("edges").is_err());
+        assert!(parse_table("corner@sym48").is_err());
+        assert!(parse_table("corner@flip").is_err());
+    }
+
+    #[test]
+    fn option_checks() {
+        let mod3 = TableOptions {
+            encoding: Encoding::Mod3,
+            ..TableOptions::default()
+        };
+        assert!(mod3.check(Metric::Htm, &[Lookup::Direct]).is_ok());
+        assert!(mod3.check(Metric::Qtm, &[Lookup::Direct]).is_err());
+        assert!(
+            mod3.check(Metric::Htm, &[Lookup::Direct, Lookup::Inverse])
+                .is_err()
+        );
+        assert!(
+            TableOptions::default()
+                .check(Metric::Htm, &[Lookup::Inverse])
+                .is_ok()
+        );
+
+        // before any table is loaded
+        let listed = ["corner", "first6edges@inverse"];
+        assert!(load_heuristics(&listed, Metric::Htm, &mod3).is_err());
+    }
+
+    #[test]
+    fn wrong_length() {
+        use crate::pruning_table::EO;
+
+        let dir = std::env::temp_dir().join(format!("cube_solver_tables_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // a header for the right table, and a checksum that matches the data, but too little data
+        let (path, kind) = byte_file::<Cube, EO>(&dir, "eo", Metric::Htm);
+        write_table(&path, &kind, &[0; 10]).unwrap();
+        let table = load_pruning_table::<Cube, EO>(&dir, "eo", Metric::Htm);
+        assert_eq!(
+            (*table).as_ref(),
+            (*PruningTable::<Cube, EO>::generate()).as_ref()
+        );
+        assert!(read_table(&path, &kind).is_ok_and(|data| data.len() == EO::MAX));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
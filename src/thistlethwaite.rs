@@ -0,0 +1,397 @@
+use std::sync::OnceLock;
+
+use crate::cube::{Cube, CubeError};
+use crate::heuristics::Heuristic;
+use crate::mv::Move;
+use crate::pruning_table::*;
+use crate::puzzle::Puzzle;
+
+/// the subgroups the cube is brought through, each one generated by fewer moves than the last
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subgroup {
+    G0,
+    G1,
+    G2,
+    G3,
+    G4,
+}
+
+impl Subgroup {
+    /// the moves that generate this subgroup
+    pub const fn moves(self) -> &'static [Move] {
+        use Move::*;
+
+        match self {
+            Subgroup::G0 => Move::ALL,
+            Subgroup::G1 => &[U, U2, U3, D, D2, D3, L, L2, L3, R, R2, R3, F2, B2],
+            Subgroup::G2 => Move::DOMINO,
+            Subgroup::G3 => &[U2, D2, L2, R2, F2, B2],
+            Subgroup::G4 => &[],
+        }
+    }
+
+    /// what has to be solved to go from the previous subgroup to this one
+    pub fn goal(self) -> &'static str {
+        match self {
+            Subgroup::G0 => "nothing",
+            Subgroup::G1 => "orient the edges",
+            Subgroup::G2 => "orient the corners and put the E slice edges in the E slice",
+            Subgroup::G3 => "put the corners in their tetrads and the M slice edges in the M slice",
+            Subgroup::G4 => "solve the cube with half turns",
+        }
+    }
+}
+
+impl std::fmt::Display for Subgroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let generators = match self {
+            Subgroup::G0 => "<U, D, L, R, F, B>",
+            Subgroup::G1 => "<U, D, L, R, F2, B2>",
+            Subgroup::G2 => "<U, D, L2, R2, F2, B2>",
+            Subgroup::G3 => "<U2, D2, L2, R2, F2, B2>",
+            Subgroup::G4 => "solved",
+        };
+        write!(f, "{self:?} {generators}")
+    }
+}
+
+/// the moves that took the cube into `reached`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Phase {
+    pub reached: Subgroup,
+    pub moves: Vec<Move>,
+}
+
+pub struct Tables {
+    pub phase1: Box<PruningTable<Cube, EO>>,
+    pub phase2: Box<PruningTable<Cube, Phase2>>,
+    pub phase3: Box<PruningTable<Cube, Phase3>>,
+    pub phase4: Box<PruningTable<Cube, Phase4>>,
+}
+
+impl Tables {
+    pub fn generate() -> Self {
+        Self {
+            phase1: PruningTable::generate(),
+            phase2: PruningTable::generate(),
+            phase3: PruningTable::generate(),
+            phase4: PruningTable::generate(),
+        }
+    }
+}
+
+/// Thistlethwaite's algorithm, solves the cube by bringing it through G0 -> G1 -> G2 -> G3 -> G4
+/// using only the moves of the current subgroup. Each phase's coordinate tells exactly which coset
+/// of the next subgroup the cube is in, so the tables hold exact distances and every phase is
+/// solved optimally by always making a move that gets one closer.
+///
+/// The centers of `cube` must be in their solved positions, states that can't be solved are
+/// refused before searching.
+pub fn thistlethwaite(cube: &Cube, tables: &Tables) -> Result<Vec<Phase>, CubeError> {
+    cube.validate()?;
+    // the subgroups are defined relative to the centers, so the cube is held the way they're
+    // oriented
+    let mut cube = cube.normalised();
+
+    let phases = [
+        descend(&mut cube, tables.phase1.as_ref(), Subgroup::G1),
+        descend(&mut cube, tables.phase2.as_ref(), Subgroup::G2),
+        descend(&mut cube, tables.phase3.as_ref(), Subgroup::G3),
+        descend(&mut cube, tables.phase4.as_ref(), Subgroup::G4),
+    ];
+
+    debug_assert!(cube.is_solved());
+    Ok(phases.into())
+}
+
+/// follows the pruning table downhill using the moves of the subgroup before `reached`
+fn descend(cube: &mut Cube, table: impl Heuristic<Cube>, reached: Subgroup) -> Phase {
+    let previous = match reached {
+        Subgroup::G1 => Subgroup::G0,
+        Subgroup::G2 => Subgroup::G1,
+        Subgroup::G3 => Subgroup::G2,
+        _ => Subgroup::G3,
+    };
+
+    let mut moves = Vec::new();
+    let mut distance = table.lower_bound(cube);
+
+    while distance > 0 {
+        let (mv, next) = previous
+            .moves()
+            .iter()
+            .map(|&mv| (mv, &*cube * mv))
+            .find(|(_, next)| table.lower_bound(next) == distance - 1)
+            .expect("the table should have a move that gets closer to the subgroup");
+
+        moves.push(mv);
+        *cube = next;
+        distance -= 1;
+    }
+
+    Phase { reached, moves }
+}
+
+/// corner orientation and the positions of the E slice edges, 0 in G2
+pub struct Phase2;
+
+impl Coordinate<Cube> for Phase2 {
+    const MAX: usize = <(CornerOrientation, UDSlice)>::MAX;
+    const MOVES: &'static [Move] = Subgroup::G1.moves();
+
+    fn name() -> String {
+        "ThistlethwaitePhase2".to_string()
+    }
+
+    fn to_coord(state: &Cube) -> usize {
+        <(CornerOrientation, UDSlice)>::to_coord(state)
+    }
+
+    fn from_coord(coord: usize) -> Cube {
+        <(CornerOrientation, UDSlice)>::from_coord(coord)
+    }
+
+    fn set_coord(state: &mut Cube, coord: usize) {
+        <(CornerOrientation, UDSlice)>::set_coord(state, coord)
+    }
+}
+
+/// the coset of the half turn group the corner permutation is in, and the positions of the M
+/// slice edges among the U and D layer edges, 0 in G3
+pub struct Phase3;
+
+impl Phase3 {
+    /// U and D layer positions, the M slice positions first so the solved state is 0
+    const POSITIONS: [usize; 8] = [0, 2, 8, 10, 1, 3, 9, 11];
+    const M_EDGES: [u8; 4] = [0, 2, 8, 10];
+    const EDGES: usize = choose(8, 4);
+}
+
+impl Coordinate<Cube> for Phase3 {
+    const MAX: usize = CORNER_COSETS * Self::EDGES;
+    const MOVES: &'static [Move] = Move::DOMINO;
+
+    fn name() -> String {
+        "ThistlethwaitePhase3".to_string()
+    }
+
+    fn to_coord(state: &Cube) -> usize {
+        let mut k = 0;
+        let mut edges = 0;
+        for (i, &position) in Self::POSITIONS.iter().enumerate() {
+            if Self::M_EDGES.contains(&state.ep[position]) {
+                k += 1;
+                edges += choose(i, k);
+            }
+        }
+
+        let corners = corner_cosets().coset[CornerPermutation::to_coord(state)] as usize;
+        corners * Self::EDGES + edges
+    }
+
+    fn from_coord(coord: usize) -> Cube {
+        debug_assert!(coord < Self::MAX, "number {coord} out of bounds");
+
+        let corners = corner_cosets().representative[coord / Self::EDGES] as usize;
+        let mut choice = coord % Self::EDGES;
+
+        let mut cube = CornerPermutation::from_coord(corners);
+
+        let mut is_m = [false; 8];
+        for k in (1..=4).rev() {
+            let (i, x) = greatest_combination(choice, k);
+            is_m[i as usize] = true;
+            choice -= x;
+        }
+
+        let mut m_edges = Self::M_EDGES.into_iter();
+        let mut s_edges = [1, 3, 9, 11].into_iter();
+        for (position, is_m) in Self::POSITIONS.into_iter().zip(is_m) {
+            cube.ep[position] = match is_m {
+                true => m_edges.next().unwrap(),
+                false => s_edges.next().unwrap(),
+            };
+        }
+        cube
+    }
+
+    fn set_coord(state: &mut Cube, coord: usize) {
+        let cube = Self::from_coord(coord);
+        state.cp = cube.cp;
+        for i in Self::POSITIONS {
+            state.ep[i] = cube.ep[i];
+        }
+    }
+}
+
+/// the position within G3, the corner permutation among the half turn group and the order of
+/// the edges within each slice
+pub struct Phase4;
+
+impl Phase4 {
+    const SLICES: [[usize; 4]; 3] = [[0, 2, 8, 10], [1, 3, 9, 11], [4, 5, 6, 7]];
+}
+
+impl Coordinate<Cube> for Phase4 {
+    const MAX: usize = HALF_TURN_CORNERS * 24 * 24 * 24;
+    const MOVES: &'static [Move] = Subgroup::G3.moves();
+
+    fn name() -> String {
+        "ThistlethwaitePhase4".to_string()
+    }
+
+    fn to_coord(state: &Cube) -> usize {
+        let corners = corner_cosets()
+            .half_turn
+            .binary_search(&(CornerPermutation::to_coord(state) as u16))
+            .expect("corners should be in the half turn group");
+
+        Self::SLICES.iter().fold(corners, |acc, slice| {
+            let order = slice.map(|i| slice.iter().position(|&j| j == state.ep[i] as usize));
+            let order = order.map(|x| x.expect("edges should be in their slices") as u8);
+            acc * 24 + permutation_to_coord(&order)
+        })
+    }
+
+    fn from_coord(coord: usize) -> Cube {
+        debug_assert!(coord < Self::MAX, "number {coord} out of bounds");
+
+        let mut n = coord;
+        let mut cube = Cube::solved();
+        for slice in Self::SLICES.iter().rev() {
+            let order = permutation_from_coord::<4>(n % 24);
+            n /= 24;
+            for (&i, j) in slice.iter().zip(order) {
+                cube.ep[i] = slice[j as usize] as u8;
+            }
+        }
+
+        cube.cp = CornerPermutation::from_coord(corner_cosets().half_turn[n] as usize).cp;
+        cube
+    }
+
+    fn set_coord(state: &mut Cube, coord: usize) {
+        let cube = Self::from_coord(coord);
+        state.cp = cube.cp;
+        state.ep = cube.ep;
+    }
+}
+
+/// number of corner permutations reachable with half turns
+const HALF_TURN_CORNERS: usize = 96;
+const CORNER_COSETS: usize = 40320 / HALF_TURN_CORNERS;
+
+struct CornerCosets {
+    /// the coset of every corner permutation coordinate, numbered in order of their smallest
+    /// member so the half turn group itself is 0
+    coset: Vec<u16>,
+    /// the smallest corner permutation coordinate of each coset
+    representative: Vec<u16>,
+    /// the sorted corner permutation coordinates of the half turn group
+    half_turn: Vec<u16>,
+}
+
+/// splits the corner permutations into the sets `H * x` where `H` is the group generated by half
+/// turns, a state is `x` half turn moves from G3 exactly when its whole set is
+fn corner_cosets() -> &'static CornerCosets {
+    static COSETS: OnceLock<CornerCosets> = OnceLock::new();
+
+    COSETS.get_or_init(|| {
+        let half_turns = Subgroup::G3.moves().iter().map(|m| m.to_cube().cp);
+
+        let half_turns: Vec<[u8; 8]> = half_turns.collect();
+        let mut coset = vec![u16::MAX; 40320];
+        let mut representative = Vec::with_capacity(CORNER_COSETS);
+
+        for start in 0..40320 {
+            if coset[start] != u16::MAX {
+                continue;
+            }
+
+            let id = representative.len() as u16;
+            representative.push(start as u16);
+            coset[start] = id;
+
+            // flood fill by turning the corners with half turns before the rest of the state
+            let mut stack = vec![start];
+            while let Some(coord) = stack.pop() {
+                let cp = CornerPermutation::from_coord(coord).cp;
+                for h in &half_turns {
+                    let mut next = Cube::solved();
+                    next.cp = cp.map(|c| h[c as usize]);
+                    let next = CornerPermutation::to_coord(&next);
+                    if coset[next] == u16::MAX {
+                        coset[next] = id;
+                        stack.push(next);
+                    }
+                }
+            }
+        }
+
+        let half_turn = (0..40320u16).filter(|&c| coset[c as usize] == 0).collect();
+
+        CornerCosets {
+            coset,
+            representative,
+            half_turn,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mv::Move::*;
+
+    #[test]
+    fn corner_cosets_sizes() {
+        let cosets = corner_cosets();
+
+        assert_eq!(cosets.representative.len(), CORNER_COSETS);
+        assert_eq!(cosets.half_turn.len(), HALF_TURN_CORNERS);
+        assert_eq!(cosets.half_turn[0], 0);
+    }
+
+    #[test]
+    fn subgroup_coordinates() {
+        let solved = Cube::solved();
+        assert_eq!(Phase2::to_coord(&solved), 0);
+        assert_eq!(Phase3::to_coord(&solved), 0);
+        assert_eq!(Phase4::to_coord(&solved), 0);
+
+        // a G3 state is at 0 in phase 3 no matter how it's scrambled
+        let g3 = R2 * U2 * F2 * L2 * D2 * B2 * R2 * U2;
+        assert_eq!(Phase3::to_coord(&g3), 0);
+        assert_ne!(Phase4::to_coord(&g3), 0);
+    }
+
+    #[test]
+    fn subgroup_coordinates_round_trip() {
+        let g2 = U * R2 * D3 * F2 * U2 * B2 * L2 * D;
+        let coord = Phase3::to_coord(&g2);
+        assert_eq!(Phase3::to_coord(&Phase3::from_coord(coord)), coord);
+
+        let g3 = R2 * U2 * F2 * L2 * D2 * B2 * R2 * U2;
+        let coord = Phase4::to_coord(&g3);
+        assert_eq!(Phase4::from_coord(coord), g3);
+    }
+
+    #[test]
+    fn descend_reaches_subgroup() {
+        // the phase 2 and 4 tables take a while to generate, so only the small ones are used
+        let phase1 = PruningTable::<Cube, EO>::generate();
+        let mut cube = R * U2 * F3 * L * B * D * R * U * F2 * D3 * L2 * B;
+
+        let phase = descend(&mut cube, phase1.as_ref(), Subgroup::G1);
+        assert_eq!(cube.eo, [0; 12]);
+        assert!(phase.moves.iter().all(|m| Move::ALL.contains(m)));
+
+        let phase3 = PruningTable::<Cube, Phase3>::generate();
+        let mut cube = U * R2 * D3 * F2 * U2 * B2 * L2 * D * R2 * U3;
+
+        let phase = descend(&mut cube, phase3.as_ref(), Subgroup::G3);
+        assert_eq!(Phase3::to_coord(&cube), 0);
+        assert!(phase.moves.iter().all(|m| Move::DOMINO.contains(m)));
+        assert!(phase.moves.len() <= 10);
+    }
+}
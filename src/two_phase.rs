@@ -0,0 +1,315 @@
+use std::time::{Duration, Instant};
+
+use crate::cube::Cube;
+use crate::heuristics::Heuristic;
+use crate::move_table::{CoordCube, CoordinateTuple};
+use crate::mv::Move;
+use crate::progress::progress;
+use crate::pruning_table::{
+    Coordinate, CornerOrientation, CornerPermutation, EO, SlicePermutation, UDEdgePermutation,
+    UDSlice,
+};
+use crate::puzzle::Puzzle;
+
+/// every state can be brought into the domino subgroup in 12 moves
+const MAX_PHASE1: u8 = 12;
+/// every domino state can be solved in 18 moves
+const MAX_PHASE2: u8 = 18;
+
+/// the coordinates phase 1 turns, which are all solved in the domino subgroup
+pub type Phase1Cube = CoordCube<(CornerOrientation, EO, UDSlice), 3>;
+/// the coordinates phase 2 turns, the permutations left within the domino subgroup
+pub type Phase2Cube = CoordCube<(CornerPermutation, UDEdgePermutation, SlicePermutation), 3>;
+
+/// a state a phase searches, the whole cube or only the coordinates its tables look up
+pub trait PhaseState: Puzzle + Clone {
+    fn from_cube(cube: &Cube) -> Self;
+}
+
+/// a state phase 1 searches, which can tell when it reaches the domino subgroup
+pub trait Phase1State: PhaseState {
+    fn is_domino(&self) -> bool;
+}
+
+impl PhaseState for Cube {
+    fn from_cube(cube: &Cube) -> Self {
+        cube.clone()
+    }
+}
+
+impl Phase1State for Cube {
+    fn is_domino(&self) -> bool {
+        self.co == [0; 8] && self.eo == [0; 12] && UDSlice::to_coord(self) == 0
+    }
+}
+
+impl<C: CoordinateTuple<N>, const N: usize> PhaseState for CoordCube<C, N> {
+    fn from_cube(cube: &Cube) -> Self {
+        CoordCube::new(cube)
+    }
+}
+
+impl Phase1State for Phase1Cube {
+    fn is_domino(&self) -> bool {
+        self.is_solved()
+    }
+}
+
+/// Kociemba's algorithm, first brings the cube into the domino subgroup <U, D, R2, L2, F2, B2>
+/// then solves it using only those moves. The first solution is found quickly, after which
+/// longer phase 1 solutions are tried to find shorter overall solutions, until one of at most
+/// `target` moves is found or `time_limit` runs out.
+///
+/// `phase1` bounds the distance to the domino subgroup, `phase2` bounds the distance to solved for
+/// states within it. Each phase searches states of its own type, usually `Phase1Cube` and
+/// `Phase2Cube`, which are turned through move tables. There is no solution for states that fail
+/// `Cube::validate`.
+pub fn two_phase<P1, P2>(
+    cube: &Cube,
+    target: u8,
+    time_limit: Duration,
+    phase1: impl Heuristic<P1>,
+    phase2: impl Heuristic<P2>,
+) -> Option<Vec<Move>>
+where
+    P1: Phase1State,
+    P2: PhaseState,
+    for<'a> &'a P1: std::ops::Mul<Move, Output = P1>,
+    for<'a> &'a P2: std::ops::Mul<Move, Output = P2>,
+{
+    two_phase_counted(cube, target, time_limit, phase1, phase2).0
+}
+
+/// `two_phase`, along with how many nodes it searched
+pub fn two_phase_counted<P1, P2>(
+    cube: &Cube,
+    target: u8,
+    time_limit: Duration,
+    phase1: impl Heuristic<P1>,
+    phase2: impl Heuristic<P2>,
+) -> (Option<Vec<Move>>, u64)
+where
+    P1: Phase1State,
+    P2: PhaseState,
+    for<'a> &'a P1: std::ops::Mul<Move, Output = P1>,
+    for<'a> &'a P2: std::ops::Mul<Move, Output = P2>,
+{
+    if cube.validate().is_err() {
+        return (None, 0);
+    }
+    // the phases' moves keep the centers where they are, so they're solved first by holding the
+    // cube the way it's oriented
+    let cube = &cube.normalised();
+
+    let start = Instant::now();
+    let mut search = TwoPhase {
+        cube,
+        phase1,
+        phase2,
+        target,
+        deadline: start + time_limit,
+        start,
+        nodes: 0,
+        done: false,
+        best: None,
+    };
+
+    let state = P1::from_cube(cube);
+    let mut path = Vec::new();
+    for depth in phase1.lower_bound(&state)..=MAX_PHASE1 {
+        let best = search.best.as_ref().map_or(u8::MAX, |b| b.len() as u8);
+        if search.done || depth >= best {
+            break;
+        }
+
+        progress!("starting phase 1 depth {depth}...");
+        search.phase1(&state, &mut path, depth);
+    }
+
+    progress!(
+        "searched {} nodes in {:.2?}{}",
+        search.nodes,
+        start.elapsed(),
+        if Instant::now() >= search.deadline {
+            ", ran out of time"
+        } else {
+            ""
+        }
+    );
+
+    (search.best, search.nodes)
+}
+
+struct TwoPhase<'a, H1, H2> {
+    /// the state being solved, which phase 2 starts from once phase 1's moves are made
+    cube: &'a Cube,
+    phase1: H1,
+    phase2: H2,
+    target: u8,
+    deadline: Instant,
+    start: Instant,
+    nodes: u64,
+    /// set once the target is reached or the time runs out
+    done: bool,
+    best: Option<Vec<Move>>,
+}
+
+impl<H1, H2> TwoPhase<'_, H1, H2> {
+    fn phase1<P1, P2>(&mut self, state: &P1, path: &mut Vec<Move>, remaining: u8)
+    where
+        H1: Heuristic<P1>,
+        H2: Heuristic<P2>,
+        P1: Phase1State,
+        P2: PhaseState,
+        for<'a> &'a P1: std::ops::Mul<Move, Output = P1>,
+        for<'a> &'a P2: std::ops::Mul<Move, Output = P2>,
+    {
+        if remaining == 0 {
+            // a solution ending in a domino move was already tried as a shorter phase 1
+            if state.is_domino() && path.last().is_none_or(|m| !Move::DOMINO.contains(m)) {
+                let cube = path.iter().fold(self.cube.clone(), |cube, &mv| cube * mv);
+                self.start_phase2(&P2::from_cube(&cube), path);
+            }
+            return;
+        }
+
+        if self.phase1.lower_bound(state) > remaining || self.tick() {
+            return;
+        }
+
+        for &mv in Move::ALL {
+            if path.last().is_some_and(|&last| mv.skip_after(last)) {
+                continue;
+            }
+
+            path.push(mv);
+            self.phase1::<P1, P2>(&(state * mv), path, remaining - 1);
+            path.pop();
+
+            if self.done {
+                return;
+            }
+        }
+    }
+
+    fn start_phase2<P2>(&mut self, state: &P2, path: &mut Vec<Move>)
+    where
+        H2: Heuristic<P2>,
+        P2: Puzzle,
+        for<'a> &'a P2: std::ops::Mul<Move, Output = P2>,
+    {
+        let phase1_length = path.len() as u8;
+        // only look for solutions shorter than the best one so far
+        let longest = match &self.best {
+            Some(best) if best.len() as u8 <= phase1_length => return,
+            Some(best) => (best.len() as u8 - 1 - phase1_length).min(MAX_PHASE2),
+            None => MAX_PHASE2,
+        };
+
+        for depth in self.phase2.lower_bound(state)..=longest {
+            if self.phase2(state, path, depth) {
+                progress!(
+                    "found {} move solution ({phase1_length} + {depth}) after {:.2?}",
+                    path.len(),
+                    self.start.elapsed()
+                );
+
+                if path.len() as u8 <= self.target {
+                    self.done = true;
+                }
+                self.best = Some(path.clone());
+                path.truncate(phase1_length as usize);
+                return;
+            }
+
+            if self.done {
+                return;
+            }
+        }
+    }
+
+    /// leaves the solution in `path` when one is found
+    fn phase2<P2>(&mut self, state: &P2, path: &mut Vec<Move>, remaining: u8) -> bool
+    where
+        H2: Heuristic<P2>,
+        P2: Puzzle,
+        for<'a> &'a P2: std::ops::Mul<Move, Output = P2>,
+    {
+        if remaining == 0 {
+            return state.is_solved();
+        }
+
+        if self.phase2.lower_bound(state) > remaining || self.tick() {
+            return false;
+        }
+
+        for &mv in Move::DOMINO {
+            if path.last().is_some_and(|&last| mv.skip_after(last)) {
+                continue;
+            }
+
+            path.push(mv);
+            if self.phase2(&(state * mv), path, remaining - 1) {
+                return true;
+            }
+            path.pop();
+
+            if self.done {
+                return false;
+            }
+        }
+
+        false
+    }
+
+    /// counts a node, returns true once the search should stop
+    fn tick(&mut self) -> bool {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(4096) && Instant::now() >= self.deadline {
+            self.done = true;
+        }
+        self.done
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::Algorithm;
+    use crate::heuristics::ZeroBound;
+    use crate::move_table::Single;
+    use crate::pruning_table::PruningTable;
+
+    #[test]
+    fn solves_short_scrambles() {
+        let co = PruningTable::<Cube, CornerOrientation>::generate();
+        // rotations leave the cube held another way, which is solved relative to its centers
+        for scramble in ["U R2 F2 D", "R U F", "F' L D2 B", "x y", "R U x F"] {
+            let scramble: Algorithm = scramble.parse().unwrap();
+            let cube = scramble.to_cube();
+            let target = scramble.length(crate::mv::Metric::Htm) as u8;
+            let time_limit = Duration::from_secs(10);
+
+            let solution = two_phase::<Phase1Cube, Phase2Cube>(
+                &cube,
+                target,
+                time_limit,
+                Single(co.as_ref()),
+                ZeroBound,
+            )
+            .unwrap();
+
+            assert!(solution.len() <= scramble.0.len());
+            assert!(
+                (cube.clone() * Algorithm::from(solution.clone()).to_cube())
+                    .normalised()
+                    .is_solved()
+            );
+
+            // the whole cube is searched the same way, only more slowly
+            let cube_solution =
+                two_phase::<Cube, Cube>(&cube, target, time_limit, co.as_ref(), ZeroBound);
+            assert_eq!(cube_solution, Some(solution));
+        }
+    }
+}